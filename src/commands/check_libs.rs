@@ -0,0 +1,60 @@
+use super::{default_install_directory, find_in_path};
+use anyhow::{bail, Result};
+use clap::Parser;
+use log::{info, warn};
+use std::{env, path::PathBuf};
+use tokio::process::Command;
+use xlm::core::XIVLAUNCHER_BIN_FILENAME;
+
+/// Check that XLM, XIVLauncher and any other given binaries can find all of the shared libraries they need.
+#[derive(Debug, Clone, Parser)]
+pub struct CheckLibsCommand {
+    /// The location of the XIVLauncher install to check.
+    #[clap(default_value_os_t = default_install_directory(), long = "install-directory")]
+    install_directory: PathBuf,
+
+    /// An extra binary to check. Can be passed multiple times.
+    #[clap(long = "binary")]
+    binaries: Vec<PathBuf>,
+}
+
+impl CheckLibsCommand {
+    pub async fn run(self) -> Result<()> {
+        let Some(ldd) = find_in_path("ldd") else {
+            bail!("Unable to find ldd on PATH, which is needed to check shared libraries.");
+        };
+
+        let mut binaries = vec![env::current_exe()?];
+        let launcher = self.install_directory.join(XIVLAUNCHER_BIN_FILENAME);
+        if launcher.exists() {
+            binaries.push(launcher);
+        }
+        binaries.extend(self.binaries);
+
+        let mut missing_any = false;
+        for binary in binaries {
+            let output = Command::new(&ldd).arg(&binary).output().await?;
+            // Statically linked binaries make ldd fail, but have no libraries that can be missing.
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let missing = stdout
+                .lines()
+                .filter(|line| line.contains("not found"))
+                .filter_map(|line| line.split_whitespace().next())
+                .collect::<Vec<_>>();
+            if missing.is_empty() {
+                info!("{binary:?} has all of the shared libraries it needs");
+            } else {
+                missing_any = true;
+                warn!(
+                    "{binary:?} is missing the shared libraries: {}",
+                    missing.join(", ")
+                );
+            }
+        }
+
+        if missing_any {
+            bail!("Some shared libraries could not be found. Install them with your package manager, or use a statically linked (musl) build of XLM if XLM itself is affected.");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use super::{default_install_directory, is_dry_run};
+use anyhow::{bail, Result};
+use clap::{ArgGroup, Parser};
+use log::info;
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use xlm::{
+    core::XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME,
+    includes::{
+        get_cache_directory, get_logfile_path, get_previous_logfile_path, XLM_CACHE_MARKER_FILENAME,
+    },
+};
+
+/// Remove XIVLauncher installs and other state created by XLM.
+#[derive(Debug, Clone, Parser)]
+#[clap(group(
+    ArgGroup::new("targets")
+        .required(true)
+        .multiple(true)
+        .args(["install", "logs", "cache", "all"])
+))]
+pub struct CleanCommand {
+    /// Remove the XIVLauncher install at `--install-directory`.
+    #[clap(long = "install")]
+    install: bool,
+
+    /// The location of the XIVLauncher install to remove.
+    #[clap(default_value_os_t = default_install_directory(), long = "install-directory")]
+    install_directory: PathBuf,
+
+    /// Remove the XLM log file.
+    #[clap(long = "logs")]
+    logs: bool,
+
+    /// Remove the XLM cache directory.
+    #[clap(long = "cache")]
+    cache: bool,
+
+    /// Remove everything, the same as passing `--install --logs --cache`.
+    #[clap(long = "all")]
+    all: bool,
+
+    /// Don't ask for confirmation before removing anything.
+    #[clap(long = "yes", short = 'y')]
+    yes: bool,
+}
+
+impl CleanCommand {
+    pub async fn run(self) -> Result<()> {
+        // Only ever remove paths that contain a file XLM itself creates to avoid removing unrelated data.
+        let mut targets = Vec::new();
+        if self.install || self.all {
+            Self::add_target(
+                &mut targets,
+                self.install_directory.clone(),
+                Some(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME),
+            )?;
+        }
+        if self.logs || self.all {
+            Self::add_target(&mut targets, get_logfile_path(), None)?;
+            Self::add_target(&mut targets, get_previous_logfile_path(), None)?;
+        }
+        if self.cache || self.all {
+            if let Some(cache_directory) = get_cache_directory() {
+                Self::add_target(
+                    &mut targets,
+                    cache_directory,
+                    Some(XLM_CACHE_MARKER_FILENAME),
+                )?;
+            }
+        }
+
+        if targets.is_empty() {
+            info!("Nothing to clean");
+            return Ok(());
+        }
+        for target in &targets {
+            info!("Will remove {target:?}");
+        }
+        if is_dry_run() {
+            info!("Dry run enabled, nothing was removed");
+            return Ok(());
+        }
+        if !self.yes && !Self::confirm("Remove the above paths? [y/N] ")? {
+            info!("Aborted, nothing was removed");
+            return Ok(());
+        }
+
+        for target in targets {
+            if target.is_dir() {
+                fs::remove_dir_all(&target)?;
+            } else {
+                fs::remove_file(&target)?;
+            }
+            info!("Removed {target:?}");
+        }
+
+        Ok(())
+    }
+
+    /// Add a path to be removed if it exists, failing if it doesn't contain the given marker file.
+    fn add_target(targets: &mut Vec<PathBuf>, path: PathBuf, marker: Option<&str>) -> Result<()> {
+        if !fs::exists(&path)? {
+            return Ok(());
+        }
+        if let Some(marker) = marker {
+            if !fs::exists(path.join(marker))? {
+                bail!(
+                    "Refusing to remove {path:?} as it was not created by XLM (missing {marker})"
+                );
+            }
+        }
+        targets.push(path);
+        Ok(())
+    }
+
+    fn confirm(prompt: &str) -> Result<bool> {
+        print!("{prompt}");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    }
+}
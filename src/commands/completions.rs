@@ -0,0 +1,20 @@
+use anyhow::Result;
+use clap::{Command, Parser};
+use clap_complete::Shell;
+use std::io;
+
+/// Print a shell completion script for XLM to stdout.
+#[derive(Debug, Clone, Parser)]
+pub struct CompletionsCommand {
+    /// The shell to generate the completion script for.
+    #[clap(value_enum)]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    pub async fn run(self, mut command: Command) -> Result<()> {
+        let name = command.get_name().to_string();
+        clap_complete::generate(self.shell, &mut command, name, &mut io::stdout());
+        Ok(())
+    }
+}
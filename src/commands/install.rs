@@ -0,0 +1,717 @@
+use super::{default_install_directory, is_dry_run};
+use crate::ui::{LaunchUI, LaunchUIOptions};
+use anyhow::{Context, Result};
+use clap::{Args, Parser};
+use log::{error, info, warn};
+use reqwest::Url;
+use std::{fs, io::ErrorKind, os::unix::fs::PermissionsExt, path::PathBuf, time::Duration};
+use xlm::{
+    core::{
+        default_temp_directory, detect_emulator, dump_github_releases, is_update_needed,
+        remove_old_temp_files, remove_stale_artifacts, verify_installed_files, AriaSource,
+        DownloadBackend, InstallProgress, Installer, ProgressCallback, ReleaseAssetInfo,
+        ARIA2C_BIN_FILENAME, DEFAULT_KEEP_BACKUPS, DEFAULT_MIN_RELEASE_SIZE,
+        XIVLAUNCHER_BIN_FILENAME, XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME,
+    },
+    download::{
+        allow_host, github_api_url, hosts_restricted, is_offline, pin_certificate, CertificatePin,
+    },
+    includes::{get_cache_directory, get_previous_logfile_path},
+    install_manifest::{InstallManifest, InstallManifestSource},
+    signature::PublicKey,
+};
+
+/// Options controlling where XIVLauncher is obtained from and installed to.
+#[derive(Debug, Clone, Args)]
+pub struct InstallOptions {
+    /// The name of the GitHub repository owner for XIVLauncher.
+    #[clap(
+        default_value = "goatcorp",
+        long = "xlcore-repo-owner",
+        env = "XLM_XLCORE_REPO_OWNER"
+    )]
+    xlcore_repo_owner: String,
+
+    /// The name of the GitHub repository for XIVLauncher.
+    #[clap(
+        default_value = "XIVLauncher.Core",
+        long = "xlcore-repo-name",
+        env = "XLM_XLCORE_REPO_NAME"
+    )]
+    xlcore_repo_name: String,
+
+    /// The name of the release tar.gz archive that contains a self-contained XIVLauncher.
+    #[clap(
+        default_value = "XIVLauncher.Core.tar.gz",
+        long = "xlcore-release-asset",
+        env = "XLM_XLCORE_RELEASE_ASSET"
+    )]
+    xlcore_release_asset: String,
+
+    /// The URL to a release of XIVLauncher.Core. This conflicts with `xlcore-repo-owner` and `xlcore-repo-name`
+    /// as it overrides the default git-based release system.
+    ///
+    /// This should be a URL prefix that contains the following files, and is treated as a directory even if it
+    /// doesn't end with a slash:
+    ///
+    /// - A file called `version` that contains the version of the release.
+    ///
+    /// - A file with the name of `<xlcore-release-asset>` that contains the tar.gz archive of the release.
+    ///
+    /// - Optionally, a file called `manifest.sha256` containing `path  hash` lines for each file in the release archive.
+    ///   When present, every extracted file will be verified against it.
+    ///
+    /// - Optionally, a file called `CHANGELOG` describing the release, which is shown before updating.
+    #[clap(
+        long = "xlcore-web-release-url-base",
+        env = "XLM_XLCORE_WEB_RELEASE_URL_BASE",
+        conflicts_with = "xlcore_repo_name",
+        conflicts_with = "xlcore_repo_owner"
+    )]
+    xlcore_web_release_url_base: Option<Url>,
+
+    /// The URL or path of a JSON install manifest listing the assets that make up XIVLauncher, for distributions that
+    /// ship it as multiple files instead of a single release archive. Overrides the release repository or URL.
+    ///
+    /// The manifest contains a `version` and a list of `assets`, each with a `url`, a `filename`, an optional
+    /// `sha256` hash to verify it against and an optional `extract-to` directory inside of the install directory.
+    /// Assets ending with `.tar.gz` are extracted and other assets are copied. aria2c is not installed separately, so
+    /// it should be listed as an asset unless `--skip-aria-validation` is used. Options that only apply to a single
+    /// release archive, such as `--xlcore-signature-url` and `--download-cache`, can't be used with a manifest.
+    #[clap(
+        long = "install-manifest",
+        env = "XLM_INSTALL_MANIFEST",
+        value_name = "URL|FILE",
+        conflicts_with_all = [
+            "xlcore_repo_name",
+            "xlcore_repo_owner",
+            "xlcore_web_release_url_base",
+            "xlcore_signature_url",
+            "xlcore_pubkey",
+            "min_release_size",
+            "download_cache",
+            "download_backend",
+            "stream_extract",
+            "incremental_update",
+            "extract_only",
+        ]
+    )]
+    install_manifest: Option<InstallManifestSource>,
+
+    /// The URL to a detached signature of the XIVLauncher release archive.
+    /// When set, the archive will be verified against `--xlcore-pubkey` before being extracted.
+    ///
+    /// Both raw ed25519 signatures and minisign signatures (created with `minisign -l`) are supported.
+    #[clap(
+        long = "xlcore-signature-url",
+        env = "XLM_XLCORE_SIGNATURE_URL",
+        requires = "xlcore_pubkey"
+    )]
+    xlcore_signature_url: Option<Url>,
+
+    /// The SHA-256 fingerprint of a TLS certificate that connections to the `--xlcore-web-release-url-base` host
+    /// must present, either as its own certificate or in its chain. Can be passed multiple times.
+    ///
+    /// Normal certificate verification still applies, this protects against a rogue certificate authority.
+    #[clap(
+        long = "pin-cert-sha256",
+        env = "XLM_PIN_CERT_SHA256",
+        value_name = "HEX",
+        requires = "xlcore_web_release_url_base"
+    )]
+    pin_cert_sha256: Vec<CertificatePin>,
+
+    /// The base64-encoded ed25519 or minisign public key to verify `--xlcore-signature-url` with.
+    #[clap(
+        long = "xlcore-pubkey",
+        env = "XLM_XLCORE_PUBKEY",
+        requires = "xlcore_signature_url"
+    )]
+    xlcore_pubkey: Option<PublicKey>,
+
+    /// The source of the aria2c tarball containing a static compiled 'aria2c' binary.
+    /// By default an embedded tarball will be used requiring no downloads.
+    ///
+    /// The supported source types are `file:`, `url:`, `github:owner/repo:asset` or `embedded`.
+    /// A `github:` source will use the named asset from the latest release of the given repository.
+    #[clap(long = "aria-source", env = "XLM_ARIA_SOURCE", default_value_t = AriaSource::Embedded)]
+    aria_source: AriaSource,
+
+    /// The name of the aria2c binary inside of the aria2c tarball, for builds that don't name it `aria2c`.
+    #[clap(
+        default_value = ARIA2C_BIN_FILENAME,
+        long = "aria-binary-name",
+        env = "XLM_ARIA_BINARY_NAME"
+    )]
+    aria_binary_name: String,
+
+    /// The location where the XIVLauncher should be installed.
+    #[clap(
+        default_value_os_t = default_install_directory(),
+        long = "install-directory",
+        env = "XLM_INSTALL_DIRECTORY"
+    )]
+    pub install_directory: PathBuf,
+
+    /// The directory XIVLauncher is extracted into before being moved into the install directory.
+    ///
+    /// Defaults to a directory next to the install directory. If this is on a different filesystem to the
+    /// install directory, XIVLauncher will be extracted directly into the install directory instead.
+    #[clap(long = "temp-directory", env = "XLM_TEMP_DIRECTORY")]
+    temp_directory: Option<PathBuf>,
+
+    /// The file the installed version of XIVLauncher is recorded in.
+    ///
+    /// Defaults to a `versiondata` file inside of the install directory. Set this to keep version state in a
+    /// writable location when the install directory is read-only after installing.
+    #[clap(long = "versiondata-path", env = "XLM_VERSIONDATA_PATH")]
+    versiondata_path: Option<PathBuf>,
+
+    /// Keep downloaded XIVLauncher releases inside of the given directory and reuse them when installing the same
+    /// version again instead of downloading it. Defaults to XLM's cache directory when no directory is given.
+    #[clap(
+        long = "download-cache",
+        env = "XLM_DOWNLOAD_CACHE",
+        value_name = "DIR",
+        num_args = 0..=1
+    )]
+    download_cache: Option<Option<PathBuf>>,
+
+    /// Remove everything inside of XLM's temp directory next to the install directory and the previous XLM log file
+    /// once they are older than the given number of days.
+    ///
+    /// Files left behind by interrupted installs are always removed after an hour regardless of this option.
+    #[clap(long = "tmp-cleanup", env = "XLM_TMP_CLEANUP", value_name = "DAYS")]
+    tmp_cleanup: Option<u64>,
+
+    /// Skip checking for XIVLauncher updates. This will not prevent XIVLauncher from installing if it isn't installed.
+    #[clap(long = "skip-update", env = "XLM_SKIP_UPDATE")]
+    skip_update: bool,
+
+    /// Verify the installed XIVLauncher files against the release manifest even if they haven't changed since they
+    /// were last verified. Only applies to releases that provide a `manifest.sha256` file.
+    #[clap(long = "force-verify", env = "XLM_FORCE_VERIFY")]
+    force_verify: bool,
+
+    /// [Experimental] When updating, only extract files whose size or modification time differ from the installed
+    /// files and remove files that are no longer part of the release.
+    ///
+    /// Falls back to a full extraction if the release archive doesn't provide enough information to compare files.
+    #[clap(long = "incremental-update", env = "XLM_INCREMENTAL_UPDATE")]
+    incremental_update: bool,
+
+    /// [Experimental] Extract the XIVLauncher release archive while it downloads instead of afterwards, to reduce
+    /// install time on fast connections.
+    ///
+    /// The archive is still downloaded in full first when it is verified with `--xlcore-signature-url`, downloaded
+    /// with aria2c, already in the download cache, or compared against an existing install.
+    #[clap(long = "stream-extract", env = "XLM_STREAM_EXTRACT")]
+    stream_extract: bool,
+
+    /// [Advanced] When updating, only extract files from the XIVLauncher release archive whose path matches the
+    /// given pattern, leaving all other installed files as they are. Can be passed multiple times.
+    ///
+    /// `*` matches any characters including `/`, and `?` matches a single character, such as `XIVLauncher.Core`
+    /// or `lib*.so`. Everything is extracted when XIVLauncher isn't installed yet.
+    #[clap(long = "extract-only", env = "XLM_EXTRACT_ONLY", value_name = "GLOB")]
+    extract_only: Vec<String>,
+
+    /// [Experimental] How to download XIVLauncher, either `reqwest` or `aria2c`.
+    ///
+    /// `aria2c` downloads with multiple connections, which may be faster on high-latency connections. Releases are
+    /// still downloaded with `reqwest` when hosts are restricted, certificates are pinned or IPv6 is forced, as
+    /// aria2c makes its own connections and can't enforce them.
+    #[clap(
+        long = "download-backend",
+        env = "XLM_DOWNLOAD_BACKEND",
+        default_value_t = DownloadBackend::Reqwest
+    )]
+    download_backend: DownloadBackend,
+
+    /// The smallest size in bytes the XIVLauncher release archive can be. Smaller downloads are rejected as they
+    /// are likely an error page served by a misconfigured mirror rather than a release.
+    #[clap(
+        long = "min-release-size",
+        env = "XLM_MIN_RELEASE_SIZE",
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MIN_RELEASE_SIZE
+    )]
+    min_release_size: u64,
+
+    /// How many backups of previous XIVLauncher installs to keep when updating, named
+    /// `<install directory>.bak.<timestamp>`. The oldest backups beyond this are removed, and `0` disables backups.
+    #[clap(
+        long = "keep-n-backups",
+        env = "XLM_KEEP_N_BACKUPS",
+        value_name = "N",
+        default_value_t = DEFAULT_KEEP_BACKUPS
+    )]
+    keep_n_backups: usize,
+
+    /// Write the path and size of every file extracted from the XIVLauncher release archive to the log file, to
+    /// help diagnose archives that don't contain what XLM expects.
+    #[clap(long = "log-extracted-files", env = "XLM_LOG_EXTRACTED_FILES")]
+    log_extracted_files: bool,
+
+    /// [Advanced] Only warn instead of failing when the aria2c tarball doesn't contain the aria2c binary, and don't
+    /// check that it can run. For setups that provide aria2c to XIVLauncher some other way, such as a system package.
+    #[clap(long = "skip-aria-validation", env = "XLM_SKIP_ARIA_VALIDATION")]
+    skip_aria_validation: bool,
+
+    /// Write the tag, prerelease flag and assets of each release obtained from GitHub to the log file as JSON, to
+    /// help diagnose releases that don't contain the expected release asset.
+    #[clap(long = "debug-dump-release", env = "XLM_DEBUG_DUMP_RELEASE")]
+    debug_dump_release: bool,
+}
+
+impl InstallOptions {
+    /// Install XIVLauncher or update it if it is out of date, returning a summary of what happened.
+    ///
+    /// `on_install` is only called when an install is started, to create a callback for its progress.
+    pub async fn ensure_xlcore_up_to_date(
+        &self,
+        on_install: impl FnOnce() -> Option<ProgressCallback>,
+    ) -> Result<String> {
+        if self.debug_dump_release {
+            dump_github_releases();
+        }
+
+        // Checking for updates is optional when offline, but installing isn't.
+        if is_offline() {
+            if let Ok(ver) = fs::read_to_string(self.versiondata_path()) {
+                info!("Offline mode enabled, not attempting to update XIVLauncher.");
+                self.warn_if_install_incomplete(ver.trim());
+                self.verify_installed_files(ver.trim())?;
+                return Ok(String::from(
+                    "Skipped checking for XIVLauncher updates while offline",
+                ));
+            }
+        }
+
+        // Query the GitHub API or web release Url for release information.
+        let release = match (&self.install_manifest, &self.xlcore_web_release_url_base) {
+            (Some(source), _) => {
+                let manifest = InstallManifest::load(source).await?;
+                // Assets are configured by the manifest, so they may be downloaded from any host it lists.
+                for asset in &manifest.assets {
+                    if let Some(host) = asset.url.host_str() {
+                        allow_host(host);
+                    }
+                }
+                ReleaseAssetInfo::from_install_manifest(manifest)
+            }
+            (None, Some(url)) => {
+                ReleaseAssetInfo::from_url(url, &self.xlcore_release_asset).await?
+            }
+            (None, None) => {
+                ReleaseAssetInfo::from_github(
+                    &self.xlcore_repo_owner,
+                    &self.xlcore_repo_name,
+                    &self.xlcore_release_asset,
+                )
+                .await?
+            }
+        };
+        let remote_version = release.version.clone();
+
+        // Install XIVLauncher or do an update check if version data already exists locally.
+        let summary = match fs::read_to_string(self.versiondata_path()) {
+            Ok(ver) => {
+                let ver = ver.trim();
+                if !self.skip_update {
+                    if !is_update_needed(ver, &remote_version) {
+                        info!(
+                            "XIVLauncher is up to date! (local: {ver}, remote: {remote_version})"
+                        );
+                        self.verify_installed_files(ver)?;
+                        format!("XIVLauncher is up to date ({ver})")
+                    } else {
+                        info!("XIVLauncher is out of date (local: {ver}, remote: {remote_version}) - starting update");
+                        self.install_xlcore(release, on_install()).await?;
+                        info!("Successfully updated XIVLauncher to the latest version.");
+                        format!("Updated XIVLauncher to {remote_version}")
+                    }
+                } else {
+                    info!("Skip update enabled, not attempting to update XIVLauncher.");
+                    self.warn_if_install_incomplete(ver);
+                    self.verify_installed_files(ver)?;
+                    String::from("Skipped checking for XIVLauncher updates")
+                }
+            }
+            Err(err) => {
+                if err.kind() == ErrorKind::NotFound {
+                    info!("Unable to obtain local version data for XIVLauncher - installing latest release");
+                    self.install_xlcore(release, on_install()).await?;
+                    info!("Successfully installed XIVLauncher");
+                    format!("Installed XIVLauncher {remote_version}")
+                } else {
+                    error!(
+                        "Something went wrong whilst checking for XIVLauncher: {:?}",
+                        err
+                    );
+                    String::from("Unable to check for XIVLauncher updates")
+                }
+            }
+        };
+        Ok(summary)
+    }
+
+    /// Warn if the existing install looks broken, for when updates are skipped and it won't be repaired.
+    fn warn_if_install_incomplete(&self, version: &str) {
+        if version.trim().is_empty() {
+            warn!(
+                "The XIVLauncher version data at {:?} is empty, the install may be incomplete",
+                self.versiondata_path()
+            );
+        }
+        let binary_path = self.install_directory.join(XIVLAUNCHER_BIN_FILENAME);
+        match fs::metadata(&binary_path) {
+            Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 => {
+                warn!("{binary_path:?} is not executable, XIVLauncher will likely fail to start")
+            }
+            Ok(_) => {}
+            Err(err) => warn!(
+                "Unable to find XIVLauncher at {binary_path:?} ({err}) despite version data being present, \
+                run without `--skip-update` or remove the version data to reinstall it"
+            ),
+        }
+    }
+
+    /// Verify the existing install against the release manifest it was installed from, when it has changed since it
+    /// was last verified or `--force-verify` is set.
+    fn verify_installed_files(&self, version: &str) -> Result<()> {
+        let verified = verify_installed_files(
+            &self.install_directory,
+            &self.versiondata_path(),
+            version,
+            self.force_verify,
+        )
+        .context(
+            "XIVLauncher install failed verification against its release manifest, \
+            run `xlm clean --install` and launch again to reinstall it",
+        )?;
+        if verified {
+            info!("All installed XIVLauncher files match the release manifest");
+        }
+        Ok(())
+    }
+
+    /// Convert these options back into arguments, including any defaulted values.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = vec![format!(
+            "--xlcore-release-asset={}",
+            self.xlcore_release_asset
+        )];
+        match (&self.install_manifest, &self.xlcore_web_release_url_base) {
+            (Some(source), _) => args.push(format!("--install-manifest={source}")),
+            (None, Some(url)) => args.push(format!("--xlcore-web-release-url-base={url}")),
+            (None, None) => args.extend([
+                format!("--xlcore-repo-owner={}", self.xlcore_repo_owner),
+                format!("--xlcore-repo-name={}", self.xlcore_repo_name),
+            ]),
+        }
+        for pin in &self.pin_cert_sha256 {
+            args.push(format!("--pin-cert-sha256={pin}"));
+        }
+        if let (Some(signature_url), Some(public_key)) =
+            (&self.xlcore_signature_url, &self.xlcore_pubkey)
+        {
+            args.extend([
+                format!("--xlcore-signature-url={signature_url}"),
+                format!("--xlcore-pubkey={public_key}"),
+            ]);
+        }
+        args.extend([
+            format!("--aria-source={}", self.aria_source),
+            format!("--aria-binary-name={}", self.aria_binary_name),
+            format!("--install-directory={}", self.install_directory.display()),
+        ]);
+        if let Some(temp_directory) = &self.temp_directory {
+            args.push(format!("--temp-directory={}", temp_directory.display()));
+        }
+        if let Some(versiondata_path) = &self.versiondata_path {
+            args.push(format!("--versiondata-path={}", versiondata_path.display()));
+        }
+        match &self.download_cache {
+            Some(Some(dir)) => args.push(format!("--download-cache={}", dir.display())),
+            Some(None) => args.push(String::from("--download-cache")),
+            None => {}
+        }
+        if let Some(days) = self.tmp_cleanup {
+            args.push(format!("--tmp-cleanup={days}"));
+        }
+        if self.skip_update {
+            args.push(String::from("--skip-update"));
+        }
+        if self.force_verify {
+            args.push(String::from("--force-verify"));
+        }
+        if self.incremental_update {
+            args.push(String::from("--incremental-update"));
+        }
+        if self.stream_extract {
+            args.push(String::from("--stream-extract"));
+        }
+        for pattern in &self.extract_only {
+            args.push(format!("--extract-only={pattern}"));
+        }
+        // These only apply to release archives, so they conflict with an install manifest.
+        if self.install_manifest.is_none() {
+            args.extend([
+                format!("--download-backend={}", self.download_backend),
+                format!("--min-release-size={}", self.min_release_size),
+            ]);
+        }
+        args.push(format!("--keep-n-backups={}", self.keep_n_backups));
+        if self.log_extracted_files {
+            args.push(String::from("--log-extracted-files"));
+        }
+        if self.skip_aria_validation {
+            args.push(String::from("--skip-aria-validation"));
+        }
+        if self.debug_dump_release {
+            args.push(String::from("--debug-dump-release"));
+        }
+        args
+    }
+
+    /// The path of the file the installed version of XIVLauncher is recorded in.
+    fn versiondata_path(&self) -> PathBuf {
+        self.versiondata_path.clone().unwrap_or_else(|| {
+            self.install_directory
+                .join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME)
+        })
+    }
+
+    /// Every url that installing XIVLauncher and aria2c with these options may send requests to.
+    pub async fn endpoints(&self) -> Result<Vec<Url>> {
+        let mut endpoints = match (&self.install_manifest, &self.xlcore_web_release_url_base) {
+            (Some(source), _) => {
+                let mut endpoints = match source {
+                    InstallManifestSource::Url(url) => vec![url.clone()],
+                    InstallManifestSource::File(_) => Vec::new(),
+                };
+                // Assets are listed inside of the manifest, so it must be loaded to know where they're downloaded from.
+                match InstallManifest::load(source).await {
+                    Ok(manifest) => {
+                        endpoints.extend(manifest.assets.into_iter().map(|asset| asset.url))
+                    }
+                    Err(err) => warn!(
+                        "Unable to load the install manifest {source} to find where its assets are downloaded from: {err:#}"
+                    ),
+                }
+                endpoints
+            }
+            (None, Some(url)) => ReleaseAssetInfo::url_endpoints(url, &self.xlcore_release_asset)?,
+            (None, None) => {
+                ReleaseAssetInfo::github_endpoints(&self.xlcore_repo_owner, &self.xlcore_repo_name)?
+            }
+        };
+        if let Some(url) = &self.xlcore_signature_url {
+            endpoints.push(url.clone());
+        }
+        match &self.aria_source {
+            AriaSource::Url(url) => endpoints.push(url.clone()),
+            AriaSource::GitHub { owner, repo, .. } => {
+                endpoints.extend(ReleaseAssetInfo::github_endpoints(owner, repo)?)
+            }
+            AriaSource::Embedded | AriaSource::File(_) => {}
+        }
+        Ok(endpoints)
+    }
+
+    /// Allow outbound requests to the hosts XIVLauncher and aria2c are configured to be obtained from, for when
+    /// outbound requests are restricted.
+    pub async fn allow_configured_hosts(&self) -> Result<()> {
+        if !hosts_restricted() {
+            return Ok(());
+        }
+        // The install manifest is loaded to find the hosts of its assets, so its own host must be allowed first.
+        if let Some(InstallManifestSource::Url(url)) = &self.install_manifest {
+            if let Some(host) = url.host_str() {
+                allow_host(host);
+            }
+        }
+        for endpoint in self.endpoints().await? {
+            if let Some(host) = endpoint.host_str() {
+                allow_host(host);
+            }
+        }
+        Ok(())
+    }
+
+    /// Pin the certificates of the web release host to the configured fingerprints.
+    pub fn pin_certificates(&self) {
+        let Some(host) = self
+            .xlcore_web_release_url_base
+            .as_ref()
+            .and_then(|url| url.host_str())
+        else {
+            return;
+        };
+        for pin in &self.pin_cert_sha256 {
+            pin_certificate(host, pin.clone());
+        }
+    }
+
+    /// Remove files left behind by previously interrupted installs.
+    pub fn remove_stale_artifacts(&self) {
+        if is_dry_run() {
+            return;
+        }
+        let temp_directory = self
+            .temp_directory
+            .clone()
+            .unwrap_or_else(|| default_temp_directory(&self.install_directory));
+        let download_cache = match &self.download_cache {
+            Some(Some(dir)) => Some(dir.clone()),
+            Some(None) => get_cache_directory(),
+            None => None,
+        };
+        let mut directories = vec![temp_directory.as_path()];
+        directories.extend(download_cache.as_deref());
+        for path in remove_stale_artifacts(&directories) {
+            info!("Removed {path:?} left behind by an interrupted install");
+        }
+
+        if let Some(days) = self.tmp_cleanup {
+            let min_age = Duration::from_secs(days * 24 * 60 * 60);
+            let mut removed = remove_old_temp_files(&self.install_directory, min_age);
+            let previous_logfile = get_previous_logfile_path();
+            let is_old = fs::metadata(&previous_logfile)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= min_age);
+            if is_old && fs::remove_file(&previous_logfile).is_ok() {
+                removed.push(previous_logfile);
+            }
+            for path in removed {
+                info!("Removed {path:?} as it is older than {days} days");
+            }
+        }
+    }
+
+    /// Warn if the install directory is inside of a Steam library, as Steam may modify or remove files inside of it
+    /// while XLM is managing them.
+    pub fn warn_if_inside_steam_library(&self) {
+        let resolved = fs::canonicalize(&self.install_directory)
+            .unwrap_or_else(|_| self.install_directory.clone());
+        if resolved
+            .components()
+            .any(|component| component.as_os_str() == "steamapps")
+        {
+            warn!(
+                "The install directory {resolved:?} is inside of a Steam library. Steam may remove or replace files XLM installs there, \
+                please use a dedicated directory outside of Steam's library folders instead."
+            );
+        }
+    }
+
+    /// Warn if XLM is running under x86_64 emulation, where the embedded aria2c binary is known to crash.
+    pub fn warn_if_emulated(&self) {
+        let Some(emulator) = detect_emulator() else {
+            return;
+        };
+        let aria_advice = match self.aria_source {
+            AriaSource::Embedded => "The embedded aria2c binary is likely to crash under emulation, use a native build with `--aria-source file:<path>` or `--aria-source url:<url>`, or provide one yourself with `--skip-aria-validation`.",
+            _ => "If aria2c crashes, use a native build of it or provide one yourself with `--skip-aria-validation`.",
+        };
+        warn!("XLM appears to be running under {emulator} rather than on an x86_64 CPU. {aria_advice}");
+    }
+
+    /// Install the given release of XIVLauncher.
+    async fn install_xlcore(
+        &self,
+        release: ReleaseAssetInfo,
+        on_progress: Option<ProgressCallback>,
+    ) -> Result<()> {
+        if is_dry_run() {
+            info!(
+                "Would install XIVLauncher {} into {:?}",
+                release.version, self.install_directory
+            );
+            return Ok(());
+        }
+        let mut installer = Installer::builder(release, &self.install_directory)
+            .aria_source(self.aria_source.clone())
+            .aria_binary_name(&self.aria_binary_name)
+            .download_backend(self.download_backend)
+            .min_release_size(self.min_release_size)
+            .keep_backups(self.keep_n_backups)
+            .log_extracted_files(self.log_extracted_files)
+            .skip_aria_validation(self.skip_aria_validation)
+            .incremental_update(self.incremental_update)
+            .extract_only(self.extract_only.clone())
+            .stream_extract(self.stream_extract);
+        if let Some(temp_directory) = &self.temp_directory {
+            installer = installer.temp_directory(temp_directory);
+        }
+        if let Some(versiondata_path) = &self.versiondata_path {
+            installer = installer.versiondata_path(versiondata_path);
+        }
+        match &self.download_cache {
+            Some(Some(dir)) => installer = installer.download_cache(dir),
+            Some(None) => match get_cache_directory() {
+                Some(dir) => installer = installer.download_cache(dir),
+                None => warn!("Unable to determine the cache directory, not caching downloads"),
+            },
+            None => {}
+        }
+        if let Some(on_progress) = on_progress {
+            installer = installer.on_progress(on_progress);
+        }
+        if let (Some(signature_url), Some(public_key)) =
+            (&self.xlcore_signature_url, &self.xlcore_pubkey)
+        {
+            installer = installer.signature(signature_url.clone(), public_key.clone());
+        }
+        installer.build().install().await
+    }
+
+    /// The url of the host that release information is fetched from.
+    pub fn release_host_url(&self) -> Url {
+        match (&self.install_manifest, &self.xlcore_web_release_url_base) {
+            (Some(InstallManifestSource::Url(url)), _) | (None, Some(url)) => url.clone(),
+            _ => github_api_url().clone(),
+        }
+    }
+}
+
+/// Install or update XIVLauncher without launching it.
+#[derive(Debug, Clone, Parser)]
+pub struct InstallCommand {
+    #[clap(flatten)]
+    pub install_options: InstallOptions,
+
+    /// Show the launch UI while XIVLauncher is being installed.
+    #[clap(long = "show-ui")]
+    show_ui: bool,
+
+    #[clap(flatten)]
+    ui_options: LaunchUIOptions,
+}
+
+impl InstallCommand {
+    pub async fn run(self) -> Result<()> {
+        self.install_options.allow_configured_hosts().await?;
+        self.install_options.pin_certificates();
+        self.install_options.warn_if_inside_steam_library();
+        self.install_options.warn_if_emulated();
+        self.install_options.remove_stale_artifacts();
+        let summary = self
+            .install_options
+            .ensure_xlcore_up_to_date(|| {
+                if !self.show_ui {
+                    return None;
+                }
+                let launch_ui = LaunchUI::new(&self.ui_options)?;
+                Some(Box::new(move |progress: &InstallProgress| {
+                    launch_ui.set_progress_text(progress.text);
+                }))
+            })
+            .await?;
+        info!("{summary}");
+        Ok(())
+    }
+}
@@ -1,17 +1,37 @@
-use crate::includes::{
-    get_launch_script, COMPATIBILITYTOOL_VDF_CONTENT, COMPATIBILITYTOOL_VDF_FILENAME,
-    TOOLMANIFEST_VDF_CONTENT, TOOLMANIFEST_VDF_FILENAME, XLM_BINARY_FILENAME,
-    XLM_COMPATDIR_DIRNAME, XLM_LAUNCHSCRIPT_FILENAME,
+use super::{
+    is_dry_run, is_valid_env_key, launch_script_install_directory, shell_split,
+    steam_compat_path_candidates, warn_if_legacy_compat_tool,
 };
+use crate::Arguments;
 use anyhow::{bail, Context, Result};
 use clap::Parser;
-use log::{debug, info};
+use dialoguer::Select;
+use log::{debug, info, warn};
 use std::{
+    env,
     fs::{self, File},
-    io::Write,
+    io::{self, IsTerminal, Write},
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
 };
+use xlm::{
+    error::XlmError,
+    includes::{
+        get_launch_script, shell_quote, COMPATIBILITYTOOL_VDF_CONTENT,
+        COMPATIBILITYTOOL_VDF_FILENAME, TOOLMANIFEST_VDF_CONTENT, TOOLMANIFEST_VDF_FILENAME,
+        XLM_BINARY_FILENAME, XLM_COMPATDIR_DIRNAME, XLM_LAUNCHSCRIPT_FILENAME, XLM_README_CONTENT,
+        XLM_README_FILENAME,
+    },
+    integrity::sha256_file,
+};
+
+/// The file Flatpak creates at the root of every sandbox, describing the sandboxed application.
+const FLATPAK_INFO_PATH: &str = "/.flatpak-info";
+/// Where Flatpak mounts the host's filesystem inside of sandboxes that are allowed to access it.
+const FLATPAK_HOST_ROOT: &str = "/run/host";
+
+/// How many seconds the launch script's guard against Steam launching XIVLauncher twice waits by default.
+const DEFAULT_LAUNCH_GUARD_SLEEP: u64 = 1;
 
 /// Install the XLM steam compatibility tool for easier launching via Steam.
 #[derive(Debug, Clone, Parser)]
@@ -19,8 +39,9 @@ pub struct InstallSteamToolCommand {
     /// The path to the 'compatibilitytools.d' folder in your steam installation directory.
     ///
     /// Please refer to your Steam installation if you don't know where this is, or use an install script instead.
+    /// When omitted from an interactive terminal, a list of detected paths will be shown to pick from.
     #[clap(long = "steam-compat-path")]
-    steam_compat_path: PathBuf,
+    steam_compat_path: Option<PathBuf>,
 
     /// Extra arguments to pass to the launch command when launching from the compatibility tool.
     /// This can usually be left blank.
@@ -31,69 +52,512 @@ pub struct InstallSteamToolCommand {
     /// This can usually be left blank.
     #[clap(long = "extra-env-vars")]
     extra_env_vars: Option<String>,
+
+    /// Path to a dotenv-style file of `KEY=VALUE` lines to pass as extra environment variables, in the same way as `--extra-env-vars`.
+    /// Blank lines and lines starting with `#` are ignored.
+    ///
+    /// Variables from this file are combined with any set via `--extra-env-vars`.
+    #[clap(long = "extra-env-file")]
+    extra_env_file: Option<PathBuf>,
+
+    /// A writable directory that XIVLauncher should be installed into instead of the compatibility tool directory.
+    ///
+    /// Use this when the compatibility tool lives in a read-only location, such as on immutable distributions,
+    /// so that only this directory needs to be writable at launch.
+    #[clap(long = "data-directory")]
+    data_directory: Option<PathBuf>,
+
+    /// Always run XIVLauncher inside of gamescope when launching from the compatibility tool, optionally with the given gamescope arguments.
+    ///
+    /// This is the same as passing `--gamescope` to the launch command.
+    #[clap(long = "gamescope", num_args = 0..=1, default_missing_value = "")]
+    gamescope: Option<String>,
+
+    /// How many seconds Steam's second invocation of the compatibility tool waits before exiting, which stops
+    /// XIVLauncher from being launched twice. Increase this if XIVLauncher launches twice on slow systems.
+    #[clap(
+        long = "launch-guard-sleep",
+        value_name = "SECS",
+        default_value_t = DEFAULT_LAUNCH_GUARD_SLEEP
+    )]
+    launch_guard_sleep: u64,
+
+    /// Only replace the XLM binary inside of an existing compatibility tool with this one, leaving its
+    /// configuration untouched.
+    #[clap(
+        long = "update-binary-only",
+        conflicts_with_all = [
+            "extra_launch_args",
+            "extra_env_vars",
+            "extra_env_file",
+            "data_directory",
+            "gamescope",
+            "launch_guard_sleep"
+        ]
+    )]
+    update_binary_only: bool,
+
+    /// Rewrite the configuration and launch script of an existing compatibility tool with the given arguments and
+    /// replace its XLM binary, leaving XIVLauncher untouched.
+    ///
+    /// The tool's data directory is kept unless `--data-directory` is given.
+    #[clap(long = "refresh", conflicts_with = "update_binary_only")]
+    refresh: bool,
+
+    /// Check that the installed compatibility tool matches what this command would install with the given
+    /// arguments, reporting any differences without modifying anything.
+    #[clap(long = "readonly-check", conflicts_with_all = ["update_binary_only", "refresh"])]
+    readonly_check: bool,
 }
 
 impl InstallSteamToolCommand {
-    pub async fn run(self) -> Result<()> {
+    pub async fn run(mut self) -> Result<()> {
+        let steam_compat_path = match &self.steam_compat_path {
+            Some(path) => path.clone(),
+            None => Self::pick_steam_compat_path()?,
+        };
+
         // Ensure the parent of "compatibilitytools.d/" (steam install) is initialised by steam first.
-        let compat_parent = self
-            .steam_compat_path
+        let compat_parent = steam_compat_path
             .parent()
             .context("unable to obtain parent folder to compat path.")?;
         if !fs::exists(compat_parent)? {
-            bail!("Unable to obtain information for the parent directory of `--steam-compat-path` ({compat_parent:?}). This is likely because you have not ran Steam for the first time.");
+            bail!(XlmError::SteamNotInitialised {
+                path: compat_parent.to_path_buf(),
+            });
+        };
+
+        warn_if_legacy_compat_tool(&steam_compat_path);
+
+        let flatpak_id = Self::flatpak_id();
+        let in_flatpak = flatpak_id.is_some();
+        if let Some(flatpak_id) = flatpak_id {
+            info!("Running inside of the {flatpak_id} Flatpak sandbox - paths under {FLATPAK_HOST_ROOT} will be written as their host paths");
+        }
+
+        if !self.readonly_check && !is_dry_run() {
+            Self::check_writable(&steam_compat_path, compat_parent)?;
+        }
+
+        if self.update_binary_only {
+            let compat_dir = steam_compat_path.join(XLM_COMPATDIR_DIRNAME);
+            if !fs::exists(compat_dir.join(XLM_LAUNCHSCRIPT_FILENAME))? {
+                bail!("The XLM compatibility tool is not installed inside of {compat_dir:?}. Please run this command without `--update-binary-only` first.");
+            }
+            if is_dry_run() {
+                info!("Would replace the XLM binary inside of {compat_dir:?}");
+                return Ok(());
+            }
+            Self::copy_binary(&compat_dir)?;
+            info!("Successfully updated the XLM binary inside of {compat_dir:?}");
+            return Ok(());
+        }
+
+        if self.refresh {
+            let compat_dir = steam_compat_path.join(XLM_COMPATDIR_DIRNAME);
+            if !fs::exists(compat_dir.join(XLM_LAUNCHSCRIPT_FILENAME))? {
+                bail!("The XLM compatibility tool is not installed inside of {compat_dir:?}. Please run this command without `--refresh` first.");
+            }
+            if self.data_directory.is_none() {
+                self.data_directory = launch_script_install_directory(&compat_dir)
+                    .and_then(|dir| dir.parent().map(Path::to_path_buf));
+            }
+        }
+
+        // Merge environment variables from the env file with any passed inline.
+        let extra_env_vars = match &self.extra_env_file {
+            Some(path) => {
+                let file_env_vars = Self::read_env_file(path)?;
+                match self.extra_env_vars {
+                    Some(inline) => Some(format!("{inline} {file_env_vars}")),
+                    None => Some(file_env_vars),
+                }
+            }
+            None => self.extra_env_vars,
         };
 
+        let extra_launch_args = match &self.gamescope {
+            Some(gamescope_args) => Some(format!(
+                "{} --gamescope={}",
+                self.extra_launch_args.unwrap_or_default(),
+                shell_quote(gamescope_args)
+            )),
+            None => self.extra_launch_args,
+        };
+        let extra_launch_args = extra_launch_args
+            .map(|args| Self::normalize_launch_args(&args))
+            .transpose()?;
+
+        let compat_dir = steam_compat_path.join(XLM_COMPATDIR_DIRNAME);
+        if self.readonly_check {
+            let data_directory = self
+                .data_directory
+                .map(|dir| Self::host_path(fs::canonicalize(&dir).unwrap_or(dir), in_flatpak));
+            return Self::check_installed(
+                &compat_dir,
+                &get_launch_script(
+                    &extra_env_vars,
+                    &extra_launch_args,
+                    data_directory.as_deref(),
+                    self.launch_guard_sleep,
+                ),
+            );
+        }
+
         // Write files
-        let compat_dir = self.steam_compat_path.join(XLM_COMPATDIR_DIRNAME);
         info!(
             "Setting up the XLM compatibility tool inside of {:?}",
             compat_dir
         );
         info!(
             "Extra launch args: {:?}, Extra env vars: {:?}",
-            self.extra_launch_args, self.extra_env_vars
+            extra_launch_args, extra_env_vars
         );
-        fs::create_dir_all(&compat_dir)?;
+        if is_dry_run() {
+            info!(
+                "Would write {COMPATIBILITYTOOL_VDF_FILENAME}, {TOOLMANIFEST_VDF_FILENAME}, {XLM_README_FILENAME}, {XLM_LAUNCHSCRIPT_FILENAME} and {XLM_BINARY_FILENAME} into {compat_dir:?}"
+            );
+            if let Some(dir) = &self.data_directory {
+                info!("Would install XIVLauncher into the data directory {dir:?}");
+            }
+            return Ok(());
+        }
+        fs::create_dir_all(&compat_dir).map_err(|source| XlmError::InstallDirNotWritable {
+            path: compat_dir.clone(),
+            source,
+        })?;
+        let data_directory = match &self.data_directory {
+            Some(dir) => {
+                fs::create_dir_all(dir).map_err(|source| XlmError::InstallDirNotWritable {
+                    path: dir.clone(),
+                    source,
+                })?;
+                let dir = Self::host_path(fs::canonicalize(dir)?, in_flatpak);
+                info!("XIVLauncher will be installed into the data directory {dir:?}");
+                Some(dir)
+            }
+            None => None,
+        };
         Self::write_compatibilitytool_vdf(&compat_dir)?;
         Self::write_toolmanifest_vdf(&compat_dir)?;
-        Self::write_script(&compat_dir, self.extra_launch_args, self.extra_env_vars)?;
-        fs::copy(
-            std::env::current_exe()?,
-            compat_dir.join(XLM_BINARY_FILENAME),
+        Self::write_readme(&compat_dir)?;
+        Self::write_script(
+            &compat_dir,
+            extra_launch_args,
+            extra_env_vars,
+            data_directory.as_deref(),
+            self.launch_guard_sleep,
         )?;
+        Self::copy_binary(&compat_dir)?;
 
         info!("Successfully set up the XLM compatibility tool - please restart Steam for it to correctly appear.");
 
         Ok(())
     }
 
+    /// The ID of the Flatpak application XLM is running inside of, if it is running inside of a Flatpak sandbox.
+    fn flatpak_id() -> Option<String> {
+        if let Some(id) = env::var_os("FLATPAK_ID") {
+            return Some(id.to_string_lossy().into_owned());
+        }
+        let info = fs::read_to_string(FLATPAK_INFO_PATH).ok()?;
+        Some(
+            info.lines()
+                .find_map(|line| line.strip_prefix("name="))
+                .unwrap_or("unknown")
+                .to_string(),
+        )
+    }
+
+    /// Rewrite a path seen from inside of a Flatpak sandbox through its view of the host filesystem to the path
+    /// Steam sees, as the launch script is run outside of XLM's sandbox.
+    fn host_path(path: PathBuf, in_flatpak: bool) -> PathBuf {
+        match path.strip_prefix(FLATPAK_HOST_ROOT) {
+            Ok(host_path) if in_flatpak => Path::new("/").join(host_path),
+            _ => path,
+        }
+    }
+
+    /// Ask the user to pick from the Steam compatibility tool paths detected on this system.
+    fn pick_steam_compat_path() -> Result<PathBuf> {
+        if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+            bail!(
+                "`--steam-compat-path` is required when not running from an interactive terminal"
+            );
+        }
+        let candidates = steam_compat_path_candidates()
+            .into_iter()
+            .filter(|path| path.parent().is_some_and(Path::exists))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            bail!("Unable to detect a Steam installation, please pass its compatibility tools path with `--steam-compat-path`");
+        }
+        let items = candidates
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        let selection = Select::new()
+            .with_prompt("Select the Steam compatibility tools path to install XLM into")
+            .items(&items)
+            .default(0)
+            .interact()?;
+        Ok(candidates[selection].clone())
+    }
+
+    /// Ensure the compatibility tools directory can be written to, or its parent if it doesn't exist yet, so sandbox
+    /// permission problems are reported up front instead of partway through writing the tool.
+    fn check_writable(compat_path: &Path, compat_parent: &Path) -> Result<()> {
+        let dir = if fs::exists(compat_path)? {
+            compat_path
+        } else {
+            compat_parent
+        };
+        let probe_path = dir.join(".xlm-write-test");
+        File::create(&probe_path)
+            .and_then(|_| fs::remove_file(&probe_path))
+            .map_err(|source| XlmError::CompatPathNotWritable {
+                path: dir.to_path_buf(),
+                source,
+            })?;
+        debug!("{dir:?} is writable");
+        Ok(())
+    }
+
+    /// Compare every file of the compatibility tool inside of the given directory to its expected content.
+    fn check_installed(dir: &Path, launch_script: &str) -> Result<()> {
+        info!("Checking the XLM compatibility tool inside of {dir:?}");
+        let current_exe = std::env::current_exe()?;
+        let expected_files = [
+            (
+                COMPATIBILITYTOOL_VDF_FILENAME,
+                COMPATIBILITYTOOL_VDF_CONTENT.to_vec(),
+            ),
+            (TOOLMANIFEST_VDF_FILENAME, TOOLMANIFEST_VDF_CONTENT.to_vec()),
+            (XLM_README_FILENAME, XLM_README_CONTENT.to_vec()),
+            (XLM_LAUNCHSCRIPT_FILENAME, launch_script.as_bytes().to_vec()),
+        ];
+
+        let mut drifted = Vec::new();
+        for (filename, expected) in expected_files {
+            match fs::read(dir.join(filename)) {
+                Ok(content) if content == expected => debug!("{filename} matches"),
+                Ok(_) => {
+                    warn!("{filename} does not match its expected content");
+                    drifted.push(filename);
+                }
+                Err(err) => {
+                    warn!("Unable to read {filename}: {err}");
+                    drifted.push(filename);
+                }
+            }
+        }
+        match sha256_file(&dir.join(XLM_BINARY_FILENAME)) {
+            Ok(hash) if hash == sha256_file(&current_exe)? => {
+                debug!("{XLM_BINARY_FILENAME} matches")
+            }
+            Ok(_) => {
+                warn!("{XLM_BINARY_FILENAME} is not the same binary as {current_exe:?}");
+                drifted.push(XLM_BINARY_FILENAME);
+            }
+            Err(err) => {
+                warn!("Unable to read {XLM_BINARY_FILENAME}: {err:#}");
+                drifted.push(XLM_BINARY_FILENAME);
+            }
+        }
+
+        if !drifted.is_empty() {
+            bail!(
+                "The XLM compatibility tool inside of {dir:?} has changed: {}",
+                drifted.join(", ")
+            );
+        }
+        info!("The XLM compatibility tool is intact");
+        Ok(())
+    }
+
+    /// Split extra launch arguments into words and quote each one so they reach the launch command exactly as
+    /// given, warning if the launch command won't accept them.
+    fn normalize_launch_args(args: &str) -> Result<String> {
+        let Some(words) = shell_split(args) else {
+            bail!(
+                "`--extra-launch-args` contains an unclosed quote or ends with a backslash: {args}"
+            );
+        };
+        if let Some(reason) = Self::launch_args_error(&words) {
+            warn!("`--extra-launch-args` are not accepted by the launch command, launching from the compatibility tool will likely fail ({reason})");
+        }
+        Ok(words
+            .iter()
+            .map(|word| shell_quote(word))
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+
+    /// Why `xlm launch` won't accept the given arguments, if it won't. Global flags such as `--offline` are
+    /// accepted alongside the launch command's own flags.
+    fn launch_args_error(words: &[String]) -> Option<String> {
+        let args = ["xlm", "launch"]
+            .into_iter()
+            .map(String::from)
+            .chain(words.iter().cloned());
+        let err = Arguments::try_parse_from(args).err()?;
+        Some(
+            err.to_string()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        )
+    }
+
+    /// Read a dotenv-style file into a string of shell-quoted `KEY='VALUE'` pairs.
+    fn read_env_file(path: &Path) -> Result<String> {
+        debug!("Reading extra env file at {path:?}");
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("unable to read extra env file at {path:?}"))?;
+
+        let mut env_vars = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((key, value)) = line.split_once('=') else {
+                bail!(XlmError::InvalidEnvFile {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                    reason: String::from("is not in the form KEY=VALUE"),
+                });
+            };
+            let key = key.trim();
+            if !is_valid_env_key(key) {
+                bail!(XlmError::InvalidEnvFile {
+                    path: path.to_path_buf(),
+                    line: index + 1,
+                    reason: format!("has an invalid variable name '{key}'"),
+                });
+            }
+            // Strip a single layer of matching quotes so values can be written as they would in a dotenv file.
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+                .unwrap_or(value);
+            env_vars.push(format!("{key}={}", shell_quote(value)));
+        }
+        Ok(env_vars.join(" "))
+    }
+
     fn write_compatibilitytool_vdf(dir: &Path) -> Result<()> {
         debug!("Writing compatibilitytool.vdf");
-        Ok(File::options()
+        let path = dir.join(COMPATIBILITYTOOL_VDF_FILENAME);
+        File::options()
             .write(true)
             .create(true)
             .truncate(true)
             .append(false)
-            .open(dir.join(COMPATIBILITYTOOL_VDF_FILENAME))?
-            .write_all(COMPATIBILITYTOOL_VDF_CONTENT)?)
+            .open(&path)?
+            .write_all(COMPATIBILITYTOOL_VDF_CONTENT)?;
+        Self::verify_vdf(
+            &path,
+            COMPATIBILITYTOOL_VDF_CONTENT,
+            &[
+                "compatibilitytools",
+                "compat_tools",
+                "install_path",
+                "display_name",
+            ],
+        )
     }
 
     fn write_toolmanifest_vdf(dir: &Path) -> Result<()> {
         debug!("Writing toolmanifest.vdf");
-        Ok(File::options()
+        let path = dir.join(TOOLMANIFEST_VDF_FILENAME);
+        File::options()
             .write(true)
             .create(true)
             .truncate(true)
             .append(false)
-            .open(dir.join(TOOLMANIFEST_VDF_FILENAME))?
-            .write_all(TOOLMANIFEST_VDF_CONTENT)?)
+            .open(&path)?
+            .write_all(TOOLMANIFEST_VDF_CONTENT)?;
+        Self::verify_vdf(
+            &path,
+            TOOLMANIFEST_VDF_CONTENT,
+            &["manifest", "commandline"],
+        )
+    }
+
+    /// Write a readme explaining what the compatibility tool is and how to remove it.
+    fn write_readme(dir: &Path) -> Result<()> {
+        debug!("Writing {XLM_README_FILENAME}");
+        fs::write(dir.join(XLM_README_FILENAME), XLM_README_CONTENT)?;
+        Ok(())
+    }
+
+    /// Read back a written vdf file to ensure it matches the intended content and has the keys Steam requires,
+    /// as Steam will silently ignore the compatibility tool if either file is broken.
+    fn verify_vdf(path: &Path, expected: &[u8], required_keys: &[&str]) -> Result<()> {
+        let written = fs::read(path).with_context(|| format!("unable to read back {path:?}"))?;
+        if written != expected {
+            bail!(XlmError::InvalidCompatToolFile {
+                path: path.to_path_buf(),
+                reason: format!(
+                    "{} of {} bytes match. Please check there is enough free disk space",
+                    written
+                        .iter()
+                        .zip(expected)
+                        .take_while(|(written, expected)| written == expected)
+                        .count(),
+                    expected.len()
+                ),
+            });
+        }
+
+        let content = String::from_utf8_lossy(&written);
+        for key in required_keys {
+            let quoted_key = format!("\"{key}\"");
+            if !content
+                .lines()
+                .any(|line| line.trim_start().starts_with(&quoted_key))
+            {
+                bail!(XlmError::InvalidCompatToolFile {
+                    path: path.to_path_buf(),
+                    reason: format!("missing the required key {quoted_key}"),
+                });
+            }
+        }
+        debug!("Verified contents of {path:?}");
+        Ok(())
+    }
+
+    /// Copy the running XLM binary into the compatibility tool directory.
+    ///
+    /// Nothing is copied if the running binary is the one inside of the directory, as copying a file onto itself
+    /// truncates it.
+    fn copy_binary(dir: &Path) -> Result<()> {
+        let current_exe = env::current_exe()?;
+        let destination = dir.join(XLM_BINARY_FILENAME);
+        if let (Ok(current_exe), Ok(destination)) = (
+            fs::canonicalize(&current_exe),
+            fs::canonicalize(&destination),
+        ) {
+            if current_exe == destination {
+                info!("XLM is running from {destination:?} already, not replacing the XLM binary");
+                return Ok(());
+            }
+        }
+        debug!("Copying XLM binary");
+        fs::copy(current_exe, destination)?;
+        Ok(())
     }
 
     fn write_script(
         dir: &Path,
         extra_launch_args: Option<String>,
         extra_env_vars: Option<String>,
+        data_directory: Option<&Path>,
+        launch_guard_sleep: u64,
     ) -> Result<()> {
         debug!("Writing script");
         // Write the launcher script and ensure it's executable.
@@ -106,7 +570,95 @@ impl InstallSteamToolCommand {
         let mut permissions = file.metadata()?.permissions();
         permissions.set_mode(0o755);
         file.set_permissions(permissions)?;
-        file.write_all(get_launch_script(&extra_env_vars, &extra_launch_args).as_bytes())?;
+        file.write_all(
+            get_launch_script(
+                &extra_env_vars,
+                &extra_launch_args,
+                data_directory,
+                launch_guard_sleep,
+            )
+            .as_bytes(),
+        )?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn normalize_launch_args_quotes_each_word() {
+        assert_eq!(
+            InstallSteamToolCommand::normalize_launch_args(
+                "--skip-update --xlcore-repo-name 'XIVLauncher Core'"
+            )
+            .unwrap(),
+            "'--skip-update' '--xlcore-repo-name' 'XIVLauncher Core'"
+        );
+    }
+
+    #[test]
+    fn normalize_launch_args_escapes_single_quotes() {
+        assert_eq!(
+            InstallSteamToolCommand::normalize_launch_args(r#"--launcher-env "NAME=it's""#)
+                .unwrap(),
+            r"'--launcher-env' 'NAME=it'\''s'"
+        );
+    }
+
+    #[test]
+    fn normalize_launch_args_rejects_unclosed_quote() {
+        assert!(
+            InstallSteamToolCommand::normalize_launch_args("--launcher-env 'NAME=value").is_err()
+        );
+    }
+
+    #[test]
+    fn launch_args_error_accepts_launch_and_global_flags() {
+        assert_eq!(
+            InstallSteamToolCommand::launch_args_error(&words(&[
+                "--skip-update",
+                "--offline",
+                "--dry-run",
+                "-v",
+                "--user-agent",
+                "XLM"
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn launch_args_error_rejects_unknown_flags() {
+        assert!(InstallSteamToolCommand::launch_args_error(&words(&["--skip-updates"])).is_some());
+    }
+
+    #[test]
+    fn host_path_strips_host_root_inside_flatpak() {
+        assert_eq!(
+            InstallSteamToolCommand::host_path(PathBuf::from("/run/host/home/user/xlm"), true),
+            PathBuf::from("/home/user/xlm")
+        );
+    }
+
+    #[test]
+    fn host_path_keeps_paths_outside_host_root() {
+        assert_eq!(
+            InstallSteamToolCommand::host_path(PathBuf::from("/home/user/xlm"), true),
+            PathBuf::from("/home/user/xlm")
+        );
+    }
+
+    #[test]
+    fn host_path_keeps_host_root_outside_flatpak() {
+        assert_eq!(
+            InstallSteamToolCommand::host_path(PathBuf::from("/run/host/home/user/xlm"), false),
+            PathBuf::from("/run/host/home/user/xlm")
+        );
+    }
+}
@@ -1,64 +1,119 @@
-use crate::{includes::ARIA2C_TARBALL_CONTENT, ui::LaunchUI};
+use super::{
+    default_install_directory, find_in_path, install::InstallOptions, is_dry_run, is_valid_env_key,
+};
+use crate::{
+    protocol,
+    ui::{LaunchUI, LaunchUIOptions},
+};
 use anyhow::{bail, Context, Result};
-use bytes::{Buf, Bytes};
 use clap::Parser;
-use flate2::read::GzDecoder;
-use log::{debug, error, info};
-use reqwest::Url;
+use log::{debug, error, info, warn};
+use notify_rust::Notification;
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::VecDeque,
     env,
-    error::Error,
+    ffi::OsString,
     fmt::Display,
-    fs::{self, File},
-    io::{ErrorKind, Write},
-    path::PathBuf,
+    fs, io, iter,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
     primitive,
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
 };
-use tar::Archive;
-use tokio::process::Command;
+use xlm::{
+    core::{InstallProgress, ProgressCallback, XIVLAUNCHER_BIN_FILENAME},
+    download::{hosts_restricted, is_offline, wait_for_reachable},
+    error::XlmError,
+    includes::{XLM_COMPAT_XLCORE_DIRNAME, XLM_LAUNCHSCRIPT_FILENAME},
+};
+
+/// The bit of the `CAP_SYS_NICE` capability in `/proc/self/status`, which allows raising process priority.
+const CAP_SYS_NICE_BIT: u32 = 23;
+
+/// How many bytes of a single line of XIVLauncher's output are logged in one message, longer lines are split.
+const LAUNCHER_OUTPUT_MAX_LINE_LEN: usize = 8 * 1024;
 
-const XIVLAUNCHER_BIN_FILENAME: &str = "XIVLauncher.Core";
-const XIVLAUNCHER_VERSION_REMOTE_FILENAME: &str = "version";
-const XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME: &str = "versiondata";
+/// How many of the last lines of XIVLauncher's output are kept to be logged if it fails the launch health check.
+const LAUNCHER_OUTPUT_TAIL_LINES: usize = 20;
 
-#[derive(Default, Clone, Debug)]
-enum AriaSource {
+/// How long to wait for the desktop notification service to accept a notification before giving up on it.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether a warning has been logged about desktop notifications being unavailable, so it is only logged once.
+static NOTIFICATION_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// The file inside of the install directory the configuration of the last successful launch is written to.
+const LAST_CONFIG_FILENAME: &str = ".xlm-last-config.json";
+
+/// The effective configuration of a successful launch, written so it can be inspected or reused later.
+#[derive(Debug, Serialize, Deserialize)]
+struct LastConfig {
+    /// The version of XLM that launched with this configuration.
+    xlm_version: String,
+    /// The launch arguments including defaulted values, without sensitive values such as launcher environment variables.
+    args: Vec<String>,
+}
+
+/// An environment variable in the form of `KEY=VALUE`.
+#[derive(Clone, Debug)]
+struct EnvVar {
+    key: String,
+    value: String,
+}
+
+impl FromStr for EnvVar {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or("environment variables must be in the form KEY=VALUE")?;
+        if !is_valid_env_key(key) {
+            return Err("environment variable name must only contain letters, numbers and underscores and not start with a number");
+        }
+        Ok(Self {
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// The secrets provider XIVLauncher should use to store credentials.
+#[derive(Default, Clone, Copy, Debug)]
+enum SecretProvider {
     #[default]
-    Embedded,
-    Url(Url),
-    File(PathBuf),
+    Auto,
+    Libsecret,
+    File,
 }
 
-impl FromStr for AriaSource {
+impl FromStr for SecretProvider {
     type Err = &'static str;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "embedded" => Ok(Self::Embedded),
-            _ if s.starts_with("url:") => Ok(Self::Url(
-                Url::parse(&s.chars().skip(4).collect::<String>()).unwrap(),
-            )),
-            _ if s.starts_with("file:") => {
-                let s = s.chars().skip(5).collect::<String>();
-                if !fs::exists(&s)
-                    .context("exists check operation failed")
-                    .unwrap()
-                {
-                    return Err("unable to find file at given path");
-                }
-                Ok(Self::File(PathBuf::from(s)))
-            }
-            _ => Err("valid sources are 'embedded', 'url:' or 'file:'"),
+            "auto" => Ok(Self::Auto),
+            "libsecret" => Ok(Self::Libsecret),
+            "file" => Ok(Self::File),
+            _ => Err("valid secret providers are 'auto', 'libsecret' or 'file'"),
         }
     }
 }
 
-impl Display for AriaSource {
+impl Display for SecretProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self {
-            AriaSource::Embedded => write!(f, "embedded"),
-            AriaSource::File(_) => write!(f, "file:"),
-            AriaSource::Url(_) => write!(f, "url:"),
+            SecretProvider::Auto => write!(f, "auto"),
+            SecretProvider::Libsecret => write!(f, "libsecret"),
+            SecretProvider::File => write!(f, "file"),
         }
     }
 }
@@ -66,272 +121,760 @@ impl Display for AriaSource {
 /// Install or update XIVLauncher and then open it.
 #[derive(Debug, Clone, Parser)]
 pub struct LaunchCommand {
-    /// The name of the GitHub repository owner for XIVLauncher.
-    #[clap(default_value = "goatcorp", long = "xlcore-repo-owner")]
-    xlcore_repo_owner: String,
+    #[clap(flatten)]
+    pub install_options: InstallOptions,
 
-    /// The name of the GitHub repository for XIVLauncher.
-    #[clap(default_value = "XIVLauncher.Core", long = "xlcore-repo-name")]
-    xlcore_repo_name: String,
+    /// Use a fallback secrets provider with XIVLauncher instead of the system provided.
+    /// Used when no system secrets provider is available and credentials should still be saved.
+    ///
+    /// This is the same as `--secret-provider file`.
+    #[clap(
+        long = "use-fallback-secret-provider",
+        env = "XLM_USE_FALLBACK_SECRET_PROVIDER",
+        conflicts_with = "secret_provider"
+    )]
+    use_fallback_secret_provider: bool,
 
-    /// The name of the release tar.gz archive that contains a self-contained XIVLauncher.
+    /// The secrets provider XIVLauncher should store credentials with.
+    ///
+    /// `auto` leaves the choice to XIVLauncher, `libsecret` uses the system secrets provider and `file` uses
+    /// XIVLauncher's fallback file storage.
     #[clap(
-        default_value = "XIVLauncher.Core.tar.gz",
-        long = "xlcore-release-asset"
+        long = "secret-provider",
+        env = "XLM_SECRET_PROVIDER",
+        default_value_t = SecretProvider::Auto
     )]
-    xlcore_release_asset: String,
+    secret_provider: SecretProvider,
 
-    /// The URL to a release of XIVLauncher.Core. This conflicts with `xlcore-repo-owner` and `xlcore-repo-name`
-    /// as it overrides the default git-based release system.
+    /// Run the launcher in Steam compatibility tool mode.
     ///
-    /// This should be a URL prefix that contains:
+    /// This should be disabled if launching standalone not from a Steam compatibility tool. In compatibility tool
+    /// mode XIVLauncher is started with `XL_SCT=1`, and `LD_PRELOAD` is moved into `XL_PRELOAD` so the Steam overlay
+    /// is only loaded into the game. When disabled, neither `XL_SCT` nor `XL_PRELOAD` are set and `LD_PRELOAD`
+    /// is passed to XIVLauncher unchanged.
+    #[clap(
+        default_value_t = true,
+        long = "run-as-steam-compat-tool",
+        env = "XLM_RUN_AS_STEAM_COMPAT_TOOL"
+    )]
+    run_as_steam_compat_tool: primitive::bool,
+
+    /// Run the launcher in Steam compatibility tool mode even when `--run-as-steam-compat-tool` is disabled, such
+    /// as by its environment variable, to reproduce compatibility tool issues outside of Steam.
+    #[clap(long = "force-steam-compat-tool", env = "XLM_FORCE_STEAM_COMPAT_TOOL")]
+    force_steam_compat_tool: bool,
+
+    /// Send a desktop notification summarising whether XIVLauncher was installed, updated or already up to date.
     ///
-    /// - A file called `version` that contains the version of the release.
+    /// Does nothing if no notification service is available over D-Bus.
+    #[clap(long = "notify", env = "XLM_NOTIFY")]
+    notify: bool,
+
+    /// An extra environment variable in the form of `KEY=VALUE` to set for XIVLauncher. Can be passed multiple times.
+    #[clap(long = "launcher-env", env = "XLM_LAUNCHER_ENV")]
+    launcher_env: Vec<EnvVar>,
+
+    /// Path to a file of extra arguments to pass to XIVLauncher, one per line.
+    /// Blank lines and lines starting with `#` are ignored.
+    #[clap(long = "launcher-arg-file", env = "XLM_LAUNCHER_ARG_FILE")]
+    launcher_arg_file: Option<PathBuf>,
+
+    /// Path to the XIVLauncher binary to launch, for when it is kept separately from the rest of the install.
     ///
-    /// - A file with the name of `<xlcore-release-asset>` that contains the tar.gz archive of the release.
+    /// XIVLauncher is still installed and updated inside of the install directory, only the binary that is
+    /// launched changes.
     #[clap(
-        long = "xlcore-web-release-url-base",
-        conflicts_with = "xlcore_repo_name",
-        conflicts_with = "xlcore_repo_owner"
+        long = "launcher-binary-path",
+        env = "XLM_LAUNCHER_BINARY_PATH",
+        value_name = "PATH"
     )]
-    xlcore_web_release_url_base: Option<Url>,
+    launcher_binary_path: Option<PathBuf>,
 
-    /// The source of the aria2c tarball containing a static compiled 'aria2c' binary.
-    /// By default an embedded tarball will be used requiring no downloads.
+    /// Run XIVLauncher inside of gamescope, optionally with the given whitespace-separated gamescope arguments.
     ///
-    /// The supported source types are `file:`, `url:` or `embedded`.
-    #[clap(long = "aria-source", default_value_t = AriaSource::Embedded)]
-    aria_source: AriaSource,
+    /// Requires gamescope to be installed and available on PATH.
+    #[clap(long = "gamescope", env = "XLM_GAMESCOPE", num_args = 0..=1, default_missing_value = "")]
+    gamescope: Option<String>,
 
-    /// The location where the XIVLauncher should be installed.
-    #[clap(default_value = dirs::data_local_dir().unwrap().join("xlcore").into_os_string(), long = "install-directory")]
-    install_directory: PathBuf,
+    /// Run XIVLauncher with the given niceness, from -20 (highest priority) to 19 (lowest priority).
+    ///
+    /// A positive value stops XIVLauncher from competing with games already running on constrained devices.
+    /// Negative values require running as root or with the `CAP_SYS_NICE` capability.
+    #[clap(
+        long = "launcher-nice",
+        env = "XLM_LAUNCHER_NICE",
+        value_name = "N",
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i8).range(-20..=19)
+    )]
+    launcher_nice: Option<i8>,
 
-    /// Use a fallback secrets provider with XIVLauncher instead of the system provided.
-    /// Used when no system secrets provider is available and credentials should still be saved.
-    #[clap(long = "use-fallback-secret-provider")]
-    use_fallback_secret_provider: bool,
+    /// Wait up to the given number of seconds for the release host to become reachable before checking for updates.
+    ///
+    /// Useful when XLM is started before the network is ready, such as on boot. If the host is still unreachable
+    /// and XIVLauncher is already installed, it will be launched without checking for updates.
+    #[clap(
+        long = "wait-for-network",
+        env = "XLM_WAIT_FOR_NETWORK",
+        value_name = "SECS"
+    )]
+    wait_for_network: Option<u64>,
 
-    /// Run the launcher in Steam compatibility tool mode.
+    /// Arguments passed to the compatibility tool by Steam, which are forwarded to XIVLauncher unchanged.
     ///
-    /// This should be disabled if launching standalone not from a Steam compatibility tool.
-    #[clap(default_value_t = true, long = "run-as-steam-compat-tool")]
-    run_as_steam_compat_tool: primitive::bool,
+    /// This consumes every argument after it, so it must be passed last.
+    #[clap(long = "steam-app-args", num_args = 0.., allow_hyphen_values = true)]
+    steam_app_args: Vec<String>,
+
+    /// Write machine-readable progress events to stdout for frontends that embed XLM, moving logs to stderr.
+    ///
+    /// Events are written one per line, such as `PHASE extracting` or `PROGRESS 35 Extracting XIVLauncher`.
+    #[clap(long = "progress-protocol", env = "XLM_PROGRESS_PROTOCOL")]
+    pub progress_protocol: bool,
+
+    /// Don't open the launch UI window while installing XIVLauncher, for frontends that embed XLM and show their
+    /// own progress. Progress is still written to the terminal log and with `--progress-protocol` if enabled.
+    #[clap(long = "no-spawn-ui", env = "XLM_NO_SPAWN_UI")]
+    no_spawn_ui: bool,
+
+    /// Write the output of XIVLauncher to the XLM log file as well as the terminal, to help diagnose launcher crashes.
+    #[clap(long = "capture-launcher-output", env = "XLM_CAPTURE_LAUNCHER_OUTPUT")]
+    capture_launcher_output: bool,
 
-    /// Skip checking for XIVLauncher updates. This will not prevent XIVLauncher from installing if it isn't installed.
-    #[clap(long = "skip-update")]
-    skip_update: bool,
+    /// Consider the launch failed if XIVLauncher exits unsuccessfully within the given number of seconds of being
+    /// launched, logging the end of its output and exiting with an error instead of silently stopping.
+    #[clap(
+        long = "launch-health-timeout",
+        env = "XLM_LAUNCH_HEALTH_TIMEOUT",
+        value_name = "SECS"
+    )]
+    launch_health_timeout: Option<u64>,
+
+    /// A program to run after XIVLauncher exits.
+    ///
+    /// The exit code of XIVLauncher is passed as the first argument and in the `XLM_EXIT_CODE` environment
+    /// variable, or `unknown` if it was killed by a signal.
+    #[clap(
+        long = "on-exit-command",
+        env = "XLM_ON_EXIT_COMMAND",
+        value_name = "PROGRAM"
+    )]
+    on_exit_command: Option<PathBuf>,
+
+    /// Launch with the configuration recorded by the last successful launch inside of the install directory.
+    ///
+    /// Other launch options are ignored apart from `--install-directory`, `--steam-app-args`, `--progress-protocol`,
+    /// `--no-spawn-ui` and the launch UI options. Launcher environment variables are not recorded and must be passed
+    /// again.
+    #[clap(long = "reuse-last-config", env = "XLM_REUSE_LAST_CONFIG")]
+    reuse_last_config: bool,
+
+    #[clap(flatten)]
+    ui_options: LaunchUIOptions,
 }
 
 impl LaunchCommand {
-    pub async fn run(self) -> anyhow::Result<()> {
+    pub async fn run(mut self) -> anyhow::Result<()> {
+        if self.reuse_last_config {
+            self = self.with_last_config()?;
+        }
         debug!("Attempting launch with args: {self:?}");
 
-        // Query the GitHub API or web release Url for release information.
-        let (remote_version, remote_release_url) = match self.xlcore_web_release_url_base {
-            Some(url) => Self::get_release_web(url, &self.xlcore_release_asset).await?,
-            None => {
-                Self::get_release_github(
-                    &self.xlcore_repo_owner,
-                    &self.xlcore_repo_name,
-                    &self.xlcore_release_asset,
-                )
-                .await?
+        if dirs::data_local_dir().is_none()
+            && self.install_options.install_directory == default_install_directory()
+        {
+            warn!(
+                "Unable to determine the local data directory, defaulting the install directory to {:?}",
+                self.install_options.install_directory
+            );
+        }
+
+        if let Some(path) = &self.launcher_binary_path {
+            Self::check_executable(path)?;
+        }
+        if let Some(nice) = self.launcher_nice {
+            if nice < 0 && !Self::can_raise_priority() {
+                bail!("`--launcher-nice {nice}` requires running as root or with the CAP_SYS_NICE capability, use a value of 0 or higher instead");
             }
-        };
+        }
 
-        // Install XIVLauncher or do an update check if version data already exists locally.
-        match fs::read_to_string(
-            self.install_directory
-                .join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME),
-        ) {
-            Ok(ver) => {
-                if !self.skip_update {
-                    if ver == remote_version {
-                        info!(
-                            "XIVLauncher is up to date! (local: {ver} == remote: {remote_version})"
-                        );
-                    } else {
-                        let mut launch_ui = LaunchUI::new();
-                        info!("XIVLauncher is out of date (local {ver} != remote: {remote_version}) - starting update");
-                        Self::install_or_update_xlcore(
-                            &remote_version,
-                            remote_release_url,
-                            self.aria_source,
-                            &self.install_directory,
-                            &mut launch_ui,
-                        )
-                        .await?;
-                        info!("Successfully updated XIVLauncher to the latest version.")
-                    }
-                } else {
-                    info!("Skip update enabled, not attempting to update XIVLauncher.")
-                }
+        self.install_options.allow_configured_hosts().await?;
+        self.install_options.pin_certificates();
+        self.install_options.warn_if_inside_steam_library();
+        self.install_options.warn_if_emulated();
+        self.install_options.remove_stale_artifacts();
+
+        if self.compat_tool_mode() {
+            Self::check_compat_tool_install_directory(&self.install_options.install_directory);
+        }
+
+        // Give the network a chance to come up before checking for updates, launching an existing install if it never does.
+        let network_available = match self.wait_for_network {
+            Some(_) if is_offline() => true,
+            Some(_) if hosts_restricted() => {
+                warn!("Not waiting for the network as outbound requests are restricted to configured hosts");
+                true
             }
-            Err(err) => {
-                if err.kind() == ErrorKind::NotFound {
-                    let mut launch_ui = LaunchUI::new();
-                    info!("Unable to obtain local version data for XIVLauncher - installing latest release");
-                    Self::install_or_update_xlcore(
-                        &remote_version,
-                        remote_release_url,
-                        self.aria_source,
-                        &self.install_directory,
-                        &mut launch_ui,
-                    )
-                    .await?;
-                    info!("Successfully installed XIVLauncher")
-                } else {
-                    error!(
-                        "Something went wrong whilst checking for XIVLauncher: {:?}",
-                        err
-                    );
-                }
+            Some(secs) => {
+                let url = self.install_options.release_host_url();
+                info!("Waiting up to {secs}s for {url} to become reachable");
+                wait_for_reachable(&url, Duration::from_secs(secs)).await
             }
+            None => true,
         };
+        let summary = if !network_available
+            && fs::exists(
+                self.install_options
+                    .install_directory
+                    .join(XIVLAUNCHER_BIN_FILENAME),
+            )? {
+            warn!("Network did not become available in time, launching the existing XIVLauncher install without checking for updates");
+            String::from("Skipped checking for XIVLauncher updates as the network was unavailable")
+        } else {
+            if self.progress_protocol {
+                protocol::emit_phase("checking");
+            }
+            self.install_options
+                .ensure_xlcore_up_to_date(|| self.install_progress_callback())
+                .await?
+        };
+        if self.notify && !is_dry_run() {
+            Self::send_notification(&summary).await;
+        }
 
         info!("Starting XIVLauncher");
+        if self.progress_protocol {
+            protocol::emit_phase("launching");
+        }
 
-        let mut cmd = Command::new(self.install_directory.join(XIVLAUNCHER_BIN_FILENAME));
-        if self.use_fallback_secret_provider {
-            cmd.env("XL_SECRET_PROVIDER", "FILE");
+        let launcher_path = match &self.launcher_binary_path {
+            Some(path) => path.clone(),
+            None => self
+                .install_options
+                .install_directory
+                .join(XIVLAUNCHER_BIN_FILENAME),
+        };
+        // Lets tests check how XIVLauncher would be launched with a stub, such as one that prints its environment.
+        #[cfg(feature = "test-hooks")]
+        let launcher_path = match env::var_os("XLM_TEST_LAUNCHER") {
+            Some(stub) => {
+                warn!("Launching the test launcher {stub:?} instead of {launcher_path:?}");
+                PathBuf::from(stub)
+            }
+            None => launcher_path,
+        };
+        let mut program: Vec<OsString> = vec![launcher_path.into()];
+        if let Some(gamescope_args) = &self.gamescope {
+            let Some(gamescope) = find_in_path("gamescope") else {
+                error!("Please install gamescope or remove the `--gamescope` flag.");
+                bail!(XlmError::ExecutableNotFound(String::from("gamescope")));
+            };
+            info!("Running XIVLauncher inside of gamescope ({gamescope:?})");
+            program = iter::once(OsString::from(gamescope))
+                .chain(gamescope_args.split_whitespace().map(OsString::from))
+                .chain(iter::once(OsString::from("--")))
+                .chain(program)
+                .collect();
+        }
+        if let Some(nice) = self.launcher_nice {
+            let Some(nice_bin) = find_in_path("nice") else {
+                error!("Please install coreutils or remove the `--launcher-nice` flag.");
+                bail!(XlmError::ExecutableNotFound(String::from("nice")));
+            };
+            info!("Running XIVLauncher with a niceness of {nice}");
+            program = [
+                OsString::from(nice_bin),
+                OsString::from(format!("--adjustment={nice}")),
+                OsString::from("--"),
+            ]
+            .into_iter()
+            .chain(program)
+            .collect();
+        }
+        let mut cmd = Command::new(&program[0]);
+        cmd.args(&program[1..]);
+        if !self.steam_app_args.is_empty() {
+            debug!(
+                "Forwarding Steam arguments to XIVLauncher: {:?}",
+                self.steam_app_args
+            );
+            cmd.args(&self.steam_app_args);
         }
-        if self.run_as_steam_compat_tool {
-            cmd.env("XL_SCT", "1"); // Needed to trigger compatibility tool mode in XIVLauncher. Otherwise XL_PRELOAD will be ignored.
+        if let Some(path) = &self.launcher_arg_file {
+            let launcher_args = Self::read_launcher_arg_file(path)?;
+            debug!("Passing arguments from {path:?} to XIVLauncher: {launcher_args:?}");
+            cmd.args(launcher_args);
+        }
+        match self.effective_secret_provider() {
+            SecretProvider::Auto => {}
+            SecretProvider::Libsecret => {
+                cmd.env("XL_SECRET_PROVIDER", "KEYRING");
+            }
+            SecretProvider::File => {
+                cmd.env("XL_SECRET_PROVIDER", "FILE");
+            }
+        }
+        if self.compat_tool_mode() {
+            debug!("Launching XIVLauncher in Steam compatibility tool mode");
+            cmd.env("XL_SCT", "1") // Needed to trigger compatibility tool mode in XIVLauncher. Otherwise XL_PRELOAD will be ignored.
+                .env("XL_PRELOAD", env::var("LD_PRELOAD").unwrap_or_default()) // Write XL_PRELOAD so it can maybe be passed to the game later.
+                .env_remove("LD_PRELOAD"); // Completely remove LD_PRELOAD otherwise steam overlay will break the launcher text.
+        }
+        for env_var in &self.launcher_env {
+            debug!("Setting launcher environment variable {}", env_var.key);
+            cmd.env(&env_var.key, &env_var.value);
+        }
+        if self.progress_protocol {
+            // Keep stdout free for protocol events.
+            cmd.stdout(io::stderr());
+        }
+        if self.capture_launcher_output || self.launch_health_timeout.is_some() {
+            cmd.stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped());
+        }
+        if is_dry_run() {
+            let std_cmd = cmd.as_std();
+            info!(
+                "Would launch {:?} with arguments {:?}",
+                std_cmd.get_program(),
+                std_cmd.get_args().collect::<Vec<_>>()
+            );
+            return Ok(());
+        }
+        let mut child = cmd.spawn()?;
+        let launched_at = Instant::now();
+        let log_output = self.capture_launcher_output;
+        let output_tasks = [
+            child.stdout.take().map(|stdout| {
+                // Keep stdout free for protocol events.
+                let terminal: Box<dyn io::Write + Send> = match self.progress_protocol {
+                    true => Box::new(io::stderr()),
+                    false => Box::new(io::stdout()),
+                };
+                tokio::spawn(Self::forward_launcher_output(
+                    stdout, terminal, "stdout", log_output,
+                ))
+            }),
+            child.stderr.take().map(|stderr| {
+                tokio::spawn(Self::forward_launcher_output(
+                    stderr,
+                    Box::new(io::stderr()),
+                    "stderr",
+                    log_output,
+                ))
+            }),
+        ];
+        if self.progress_protocol {
+            protocol::emit_phase("running");
+        }
+        self.write_last_config();
+        let cmd = child.wait().await?;
+        let mut output_tail = Vec::new();
+        for task in output_tasks.into_iter().flatten() {
+            if let Ok(tail) = task.await {
+                output_tail.extend(tail);
+            }
         }
-        let cmd = cmd
-            .env("XL_PRELOAD", env::var("LD_PRELOAD").unwrap_or_default()) // Write XL_PRELOAD so it can maybe be passed to the game later.
-            .env_remove("LD_PRELOAD") // Completely remove LD_PRELOAD otherwise steam overlay will break the launcher text.
-            .spawn()?
-            .wait()
-            .await?;
 
         info!("XIVLauncher process exited with exit code {:?}", cmd.code());
+        if self.progress_protocol {
+            protocol::emit_exited(cmd.code());
+        }
+        if let Some(program) = &self.on_exit_command {
+            Self::run_on_exit_command(program, cmd.code()).await;
+        }
+
+        if let Some(timeout_secs) = self.launch_health_timeout {
+            if !cmd.success() && launched_at.elapsed() < Duration::from_secs(timeout_secs) {
+                if output_tail.is_empty() {
+                    error!("XIVLauncher did not write any output before exiting");
+                } else {
+                    error!(
+                        "Last output of XIVLauncher before exiting:\n{}",
+                        output_tail.join("\n")
+                    );
+                }
+                error!(
+                    "XIVLauncher crashed shortly after launching. This is commonly caused by missing libraries \
+                    (check with `xlm check-libs`), a system library or kernel incompatibility such as a `__vdso_time` \
+                    error, or a damaged install (check with `--force-verify`)."
+                );
+                bail!(XlmError::LaunchUnhealthy {
+                    status: cmd.to_string(),
+                    timeout_secs,
+                });
+            }
+        }
 
         Ok(())
     }
 
-    async fn get_release_github(
-        xlcore_repo_owner: &String,
-        xlcore_repo_name: &String,
-        xlcore_release_asset: &String,
-    ) -> Result<(String, Url)> {
-        let octocrab = octocrab::instance();
-        let repo = octocrab.repos(xlcore_repo_owner, xlcore_repo_name);
-        let release = match repo.releases().get_latest().await {
-            Ok(release) => release,
-            Err(err) => {
-                bail!(
-                    "Failed to obtain release information for {}/{}: {:?}",
-                    xlcore_repo_owner,
-                    xlcore_repo_name,
-                    err.source()
-                );
+    /// Copy output from XIVLauncher to the terminal as it arrives, optionally writing each line of it to the log
+    /// file, and return its last lines.
+    ///
+    /// Output is read in fixed-size chunks and long lines are split, so a noisy launcher can't grow memory use.
+    async fn forward_launcher_output(
+        mut output: impl AsyncRead + Unpin,
+        mut terminal: Box<dyn io::Write + Send>,
+        stream: &'static str,
+        log_lines: bool,
+    ) -> VecDeque<String> {
+        let mut tail = VecDeque::with_capacity(LAUNCHER_OUTPUT_TAIL_LINES);
+        let mut log_line = |line: &mut Vec<u8>| {
+            let text = format!(
+                "XIVLauncher {stream}: {}",
+                String::from_utf8_lossy(line).trim_end()
+            );
+            if log_lines {
+                debug!("{text}");
+            }
+            if tail.len() == LAUNCHER_OUTPUT_TAIL_LINES {
+                tail.pop_front();
             }
+            tail.push_back(text);
+            line.clear();
         };
+        let mut chunk = [0; 8 * 1024];
+        let mut line = Vec::new();
+        loop {
+            let read = match output.read(&mut chunk).await {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) => {
+                    warn!("Unable to read XIVLauncher {stream}: {err}");
+                    break;
+                }
+            };
+            let _ = terminal.write_all(&chunk[..read]);
+            for &byte in &chunk[..read] {
+                if byte == b'\n' {
+                    log_line(&mut line);
+                    continue;
+                }
+                if line.len() >= LAUNCHER_OUTPUT_MAX_LINE_LEN {
+                    log_line(&mut line);
+                }
+                line.push(byte);
+            }
+        }
+        if !line.is_empty() {
+            log_line(&mut line);
+        }
+        tail
+    }
 
-        let release_url = release
-            .assets
-            .iter()
-            .find(|asset| &asset.name == xlcore_release_asset);
+    /// Run the given program after XIVLauncher has exited, logging rather than failing if it doesn't succeed.
+    async fn run_on_exit_command(program: &Path, exit_code: Option<i32>) {
+        let exit_code = exit_code.map_or_else(|| String::from("unknown"), |code| code.to_string());
+        info!("Running on-exit command {program:?}");
+        match Command::new(program)
+            .arg(&exit_code)
+            .env("XLM_EXIT_CODE", &exit_code)
+            .env_remove("LD_PRELOAD")
+            .status()
+            .await
+        {
+            Ok(status) if status.success() => debug!("On-exit command exited successfully"),
+            Ok(status) => warn!("On-exit command {program:?} exited with {status}"),
+            Err(err) => error!("Unable to run on-exit command {program:?}: {err:?}"),
+        }
+    }
 
-        if let Some(asset) = release_url {
-            Ok((release.tag_name, asset.browser_download_url.clone()))
-        } else {
-            bail!(
-                "Failed to find asset {} in release {}",
-                xlcore_release_asset,
-                release.tag_name
-            );
+    /// The secrets provider to launch XIVLauncher with, accounting for `--use-fallback-secret-provider`.
+    fn effective_secret_provider(&self) -> SecretProvider {
+        match self.use_fallback_secret_provider {
+            true => SecretProvider::File,
+            false => self.secret_provider,
         }
     }
 
-    async fn get_release_web(base_url: Url, xlcore_release_asset: &str) -> Result<(String, Url)> {
-        let version_url = base_url.join(XIVLAUNCHER_VERSION_REMOTE_FILENAME)?;
-        let release_url = base_url.join(xlcore_release_asset)?;
+    /// Convert this command back into arguments, including any defaulted values.
+    ///
+    /// Launcher environment variables are left out as they may contain secrets, along with options that
+    /// only apply to the current launch.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = self.install_options.to_args();
+        args.extend([
+            format!("--secret-provider={}", self.effective_secret_provider()),
+            format!("--run-as-steam-compat-tool={}", self.compat_tool_mode()),
+        ]);
+        if self.notify {
+            args.push(String::from("--notify"));
+        }
+        if let Some(path) = &self.launcher_arg_file {
+            args.push(format!("--launcher-arg-file={}", path.display()));
+        }
+        if let Some(path) = &self.launcher_binary_path {
+            args.push(format!("--launcher-binary-path={}", path.display()));
+        }
+        if let Some(gamescope_args) = &self.gamescope {
+            args.push(format!("--gamescope={gamescope_args}"));
+        }
+        if let Some(nice) = self.launcher_nice {
+            args.push(format!("--launcher-nice={nice}"));
+        }
+        if let Some(secs) = self.wait_for_network {
+            args.push(format!("--wait-for-network={secs}"));
+        }
+        if self.capture_launcher_output {
+            args.push(String::from("--capture-launcher-output"));
+        }
+        if let Some(secs) = self.launch_health_timeout {
+            args.push(format!("--launch-health-timeout={secs}"));
+        }
+        if let Some(program) = &self.on_exit_command {
+            args.push(format!("--on-exit-command={}", program.display()));
+        }
+        args
+    }
+
+    /// Record the configuration of this launch inside of the install directory.
+    fn write_last_config(&self) {
+        let path = self
+            .install_options
+            .install_directory
+            .join(LAST_CONFIG_FILENAME);
+        let config = LastConfig {
+            xlm_version: env!("CARGO_PKG_VERSION").to_string(),
+            args: self.to_args(),
+        };
+        let result = serde_json::to_string_pretty(&config)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(fs::write(&path, content)?));
+        match result {
+            Ok(()) => debug!("Wrote launch configuration to {path:?}"),
+            Err(err) => warn!("Unable to write launch configuration to {path:?}: {err:?}"),
+        }
+    }
 
-        info!("XIVLauncher web release asset url:{}", release_url);
-        info!("XIVLauncher web release version url: {}", version_url);
+    /// Replace this command with the configuration recorded by the last successful launch, keeping the options
+    /// that only apply to the current launch.
+    fn with_last_config(self) -> Result<Self> {
+        let path = self
+            .install_options
+            .install_directory
+            .join(LAST_CONFIG_FILENAME);
+        let content = fs::read_to_string(&path).with_context(|| {
+            format!("unable to read the last launch configuration from {path:?}, launch without `--reuse-last-config` first")
+        })?;
+        let config: LastConfig = serde_json::from_str(&content)
+            .with_context(|| format!("last launch configuration at {path:?} is invalid"))?;
+        info!(
+            "Reusing the launch configuration recorded by XLM v{} at {path:?}",
+            config.xlm_version
+        );
+        let mut command =
+            Self::try_parse_from(iter::once(String::from("launch")).chain(config.args))
+                .with_context(|| {
+                    format!("last launch configuration at {path:?} is no longer valid")
+                })?;
+        command.steam_app_args = self.steam_app_args;
+        command.progress_protocol = self.progress_protocol;
+        command.no_spawn_ui = self.no_spawn_ui;
+        command.ui_options = self.ui_options;
+        Ok(command)
+    }
+
+    /// Whether XIVLauncher should be launched in Steam compatibility tool mode.
+    fn compat_tool_mode(&self) -> bool {
+        self.run_as_steam_compat_tool || self.force_steam_compat_tool
+    }
 
-        let response = reqwest::get(version_url).await?;
-        if !response.status().is_success() {
-            bail!("{}", format!("{:?}", response.error_for_status()))
+    /// Whether this process is allowed to give XIVLauncher a negative niceness, by having the `CAP_SYS_NICE`
+    /// capability which root has by default.
+    fn can_raise_priority() -> bool {
+        fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                let caps = status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("CapEff:"))?;
+                u64::from_str_radix(caps.trim(), 16).ok()
+            })
+            .is_some_and(|caps| caps & (1 << CAP_SYS_NICE_BIT) != 0)
+    }
+
+    /// Fail if the given path isn't an executable file.
+    fn check_executable(path: &Path) -> Result<()> {
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() && metadata.permissions().mode() & 0o111 != 0 => {
+                Ok(())
+            }
+            _ => bail!(XlmError::NotExecutable {
+                path: path.to_path_buf()
+            }),
         }
-        Ok((response.text().await?, release_url))
     }
 
-    /// Creates a new XLCore installation or overwrites an existing XLCore installion with a new one.
-    async fn install_or_update_xlcore(
-        release_version: &String,
-        release_url: Url,
-        aria_source: AriaSource,
-        install_location: &PathBuf,
-        launch_ui: &mut LaunchUI,
-    ) -> anyhow::Result<()> {
-        // Download/extract XLCore.
-        {
-            info!("Downloading XIVLauncher release from {release_url}");
-            launch_ui.set_progress_text("Downloading XIVLauncher");
-            let response = reqwest::get(release_url).await?;
-            let bytes = response.bytes().await?;
-            let mut archive = Archive::new(GzDecoder::new(bytes.reader()));
-            let _ = fs::remove_dir_all(install_location);
-            fs::create_dir_all(install_location)?;
-            info!("Unpacking XIVLauncher release tarball");
-            launch_ui.set_progress_text("Extracting XIVLauncher");
-            archive.unpack(install_location)?;
-            info!("Wrote XIVLauncher files");
-        }
-
-        // Download/extract aria2c.
-        {
-            let aria_archive_bytes = match aria_source {
-                AriaSource::Embedded => {
-                    info!("Using embedded aria2c tarball");
-                    Bytes::from_static(ARIA2C_TARBALL_CONTENT)
-                }
-                AriaSource::Url(url) => {
-                    info!("Downloading remote aria2c tarball from {url}");
-                    launch_ui.set_progress_text("Downloading aria2c");
-                    let response: reqwest::Response = reqwest::get(url).await?;
-                    response.bytes().await?
+    /// Read the arguments inside of a launcher argument file, skipping blank lines and comments.
+    fn read_launcher_arg_file(path: &Path) -> Result<Vec<String>> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("unable to read launcher argument file {path:?}"))?;
+        Ok(content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Create a callback that shows install progress in the launch UI and with the progress protocol if enabled.
+    fn install_progress_callback(&self) -> Option<ProgressCallback> {
+        let launch_ui = match self.no_spawn_ui {
+            true => None,
+            false => LaunchUI::new(&self.ui_options),
+        };
+        let progress_protocol = self.progress_protocol;
+        let last_phase = Mutex::new(None);
+        Some(Box::new(move |progress: &InstallProgress| {
+            if let Some(launch_ui) = &launch_ui {
+                launch_ui.set_progress_text(progress.text);
+            }
+            if progress_protocol {
+                let mut last_phase = last_phase.lock().unwrap();
+                if *last_phase != Some(progress.phase) {
+                    protocol::emit_phase(progress.phase);
+                    *last_phase = Some(progress.phase);
                 }
-                AriaSource::File(path) => {
-                    info!("Using local aria2c tarball at path: {path:?}");
-                    Bytes::from(fs::read(path)?)
+                protocol::emit_progress(progress.percent, progress.text);
+            }
+        }))
+    }
+
+    /// Warn if XLM is running from a compatibility tool directory but installing XIVLauncher somewhere
+    /// other than the tool's configured `xlcore` directory, as updates will not apply to the tool's install.
+    fn check_compat_tool_install_directory(install_directory: &Path) {
+        let Some(tool_dir) = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        else {
+            return;
+        };
+        if !tool_dir.join(XLM_LAUNCHSCRIPT_FILENAME).exists() {
+            return;
+        }
+
+        // The launch script exports where it expects XIVLauncher to be when a separate data directory is in use.
+        let expected = env::var_os("XLM_INSTALL_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| tool_dir.join(XLM_COMPAT_XLCORE_DIRNAME));
+        let expected = fs::canonicalize(&expected).unwrap_or(expected);
+        let resolved =
+            fs::canonicalize(install_directory).unwrap_or_else(|_| install_directory.to_path_buf());
+        if resolved != expected {
+            warn!(
+                "XLM is running as a Steam compatibility tool from {tool_dir:?} but the install directory is {resolved:?} instead of {expected:?}. \
+                Updates will be installed to {resolved:?} and not the compatibility tool's copy of XIVLauncher - check for a conflicting `--install-directory` flag."
+            );
+        }
+    }
+
+    /// Send a desktop notification over D-Bus, doing nothing if no notification service is available.
+    async fn send_notification(body: &str) {
+        let notification = Notification::new()
+            .appname("XLM")
+            .summary("XLM")
+            .body(body)
+            .finalize();
+        let reason =
+            match tokio::time::timeout(NOTIFICATION_TIMEOUT, notification.show_async()).await {
+                Ok(Ok(_)) => {
+                    debug!("Sent desktop notification: {body}");
+                    return;
                 }
+                Ok(Err(err)) => format!("no notification service is available: {err}"),
+                Err(_) => format!(
+                    "the notification service did not respond within {}s",
+                    NOTIFICATION_TIMEOUT.as_secs()
+                ),
             };
+        if NOTIFICATION_WARNED.swap(true, Ordering::Relaxed) {
+            debug!("Unable to send desktop notification as {reason}");
+        } else {
+            warn!("Unable to send desktop notifications as {reason}");
+        }
+    }
+}
 
-            let mut archive = Archive::new(GzDecoder::new(aria_archive_bytes.reader()));
-
-            info!("Unpacking aria2c tarball");
-            launch_ui.set_progress_text("Unpacking aria2c");
-            archive.unpack(install_location)?;
+#[cfg(all(test, feature = "test-hooks"))]
+mod tests {
+    use super::*;
+    use xlm::{core::XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME, download::set_offline};
 
-            info!("Ensuring aria2c tarball contained correct binary");
-            launch_ui.set_progress_text("Ensuring aria2c compatibility");
-            if !fs::exists(install_location.join("aria2c"))? {
-                error!("aria2c tarball does not contain a binary named 'aria2c' and is unusable with XIVLauncher.");
-                bail!("aria2c tarball does not contain a binary named 'aria2c' and is unusable with XIVLauncher.")
-            }
+    /// A fixture directory for a test, removed when dropped so it is cleaned up even if the test fails.
+    struct FixtureDirectory(PathBuf);
 
-            info!("Wrote aria2c binary");
+    impl Drop for FixtureDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
         }
+    }
 
-        // Write local version info for release.
-        {
-            launch_ui.set_progress_text("Writing XIVLauncher version data");
-            let mut file = File::options()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .append(false)
-                .open(install_location.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME))?;
-            file.write_all(release_version.as_bytes())?;
-            info!("Wrote versiondata with version {}", release_version);
-        }
-        launch_ui.set_progress_text("Finishing up");
+    fn write_executable(path: &Path, contents: &str) {
+        fs::write(path, contents).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
 
-        Ok(())
+    /// Launch an existing install with the given arguments, returning the environment the test launcher recorded.
+    async fn launcher_env(directory: &Path, args: &[&str]) -> Vec<String> {
+        let install_directory = directory.join("xlcore");
+        let command = LaunchCommand::try_parse_from(
+            [
+                "launch",
+                "--install-directory",
+                install_directory.to_str().unwrap(),
+            ]
+            .iter()
+            .chain(args),
+        )
+        .unwrap();
+        command.run().await.unwrap();
+        let recorded = fs::read_to_string(directory.join("launcher.env")).unwrap();
+        recorded.lines().map(String::from).collect()
+    }
+
+    #[tokio::test]
+    async fn launch_spawns_launcher_with_environment() {
+        let directory = FixtureDirectory(
+            env::temp_dir().join(format!("xlm-test-{}-launch-env", std::process::id())),
+        );
+        let install_directory = directory.0.join("xlcore");
+        fs::create_dir_all(&install_directory).unwrap();
+        fs::write(
+            install_directory.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME),
+            "1.0.0",
+        )
+        .unwrap();
+        write_executable(
+            &install_directory.join(XIVLAUNCHER_BIN_FILENAME),
+            "#!/bin/sh\n",
+        );
+        let stub = directory.0.join("launcher.sh");
+        write_executable(
+            &stub,
+            "#!/bin/sh\nenv > \"$(dirname \"$0\")/launcher.env\"\n",
+        );
+
+        // Launch the existing install without checking for updates, using the stub as XIVLauncher.
+        set_offline();
+        env::set_var("XLM_TEST_LAUNCHER", &stub);
+
+        let launched = launcher_env(&directory.0, &["--launcher-env", "XLM_TEST_VALUE=stub"]).await;
+        assert!(launched.contains(&String::from("XLM_TEST_VALUE=stub")));
+        assert!(launched.contains(&String::from("XL_SCT=1")));
+
+        let launched = launcher_env(&directory.0, &["--run-as-steam-compat-tool=false"]).await;
+        assert!(!launched.iter().any(|var| var.starts_with("XL_SCT=")));
+        assert!(!launched.iter().any(|var| var.starts_with("XL_PRELOAD=")));
+
+        let launched = launcher_env(
+            &directory.0,
+            &[
+                "--run-as-steam-compat-tool=false",
+                "--force-steam-compat-tool",
+            ],
+        )
+        .await;
+        assert!(launched.contains(&String::from("XL_SCT=1")));
     }
 }
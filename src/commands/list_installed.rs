@@ -0,0 +1,104 @@
+use super::{
+    default_install_directory, launch_script_install_directory, steam_compat_path_candidates,
+};
+use anyhow::Result;
+use clap::Parser;
+use log::{info, warn};
+use std::{collections::HashSet, fs, path::PathBuf};
+use xlm::{
+    core::{
+        is_update_needed, ReleaseAssetInfo, XIVLAUNCHER_BIN_FILENAME,
+        XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME,
+    },
+    download::{hosts_restricted, is_offline},
+    includes::{XLM_COMPATDIR_DIRNAME, XLM_COMPAT_XLCORE_DIRNAME},
+};
+
+/// List every XIVLauncher install managed by XLM that can be found, with its version and whether it is up to date.
+#[derive(Debug, Clone, Parser)]
+pub struct ListInstalledCommand {
+    /// An extra XIVLauncher install directory to check. Can be passed multiple times.
+    ///
+    /// The default install directory and the compatibility tools inside of common Steam install locations are
+    /// always checked.
+    #[clap(long = "search-path")]
+    search_paths: Vec<PathBuf>,
+
+    /// The name of the GitHub repository owner to compare installed versions against.
+    #[clap(default_value = "goatcorp", long = "xlcore-repo-owner")]
+    xlcore_repo_owner: String,
+
+    /// The name of the GitHub repository to compare installed versions against.
+    #[clap(default_value = "XIVLauncher.Core", long = "xlcore-repo-name")]
+    xlcore_repo_name: String,
+
+    /// The name of the release asset to compare installed versions against.
+    #[clap(
+        default_value = "XIVLauncher.Core.tar.gz",
+        long = "xlcore-release-asset"
+    )]
+    xlcore_release_asset: String,
+}
+
+impl ListInstalledCommand {
+    pub async fn run(self) -> Result<()> {
+        let mut candidates = vec![default_install_directory()];
+        for compat_path in steam_compat_path_candidates() {
+            let tool_dir = compat_path.join(XLM_COMPATDIR_DIRNAME);
+            candidates.push(tool_dir.join(XLM_COMPAT_XLCORE_DIRNAME));
+            candidates.extend(launch_script_install_directory(&tool_dir));
+        }
+        candidates.extend(self.search_paths.iter().cloned());
+
+        // Steam's install locations are commonly symlinked to each other, so only report each install once.
+        let mut seen = HashSet::new();
+        let installs = candidates
+            .into_iter()
+            .filter(|path| fs::exists(path.join(XIVLAUNCHER_BIN_FILENAME)).unwrap_or(false))
+            .filter(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+            .collect::<Vec<_>>();
+        if installs.is_empty() {
+            info!("Unable to find any XIVLauncher installs");
+            return Ok(());
+        }
+
+        let latest_version = if is_offline() {
+            info!("Not checking for the latest XIVLauncher version as offline mode is enabled");
+            None
+        } else if hosts_restricted() {
+            warn!("Not checking for the latest XIVLauncher version as outbound requests are restricted");
+            None
+        } else {
+            match ReleaseAssetInfo::from_github(
+                &self.xlcore_repo_owner,
+                &self.xlcore_repo_name,
+                &self.xlcore_release_asset,
+            )
+            .await
+            {
+                Ok(release) => Some(release.version),
+                Err(err) => {
+                    warn!("Unable to check for the latest XIVLauncher version: {err:#}");
+                    None
+                }
+            }
+        };
+
+        for install in installs {
+            let version = fs::read_to_string(install.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME))
+                .map(|version| version.trim().to_string())
+                .ok();
+            match (&version, &latest_version) {
+                (Some(version), Some(latest)) if !is_update_needed(version, latest) => {
+                    info!("{install:?}: XIVLauncher {version} (up to date)")
+                }
+                (Some(version), Some(latest)) => {
+                    info!("{install:?}: XIVLauncher {version} (out of date, latest is {latest})")
+                }
+                (Some(version), None) => info!("{install:?}: XIVLauncher {version}"),
+                (None, _) => info!("{install:?}: XIVLauncher (unknown version)"),
+            }
+        }
+        Ok(())
+    }
+}
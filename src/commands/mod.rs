@@ -1,2 +1,198 @@
+use log::warn;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use xlm::includes::XLM_LAUNCHSCRIPT_FILENAME;
+
+pub mod check_libs;
+pub mod clean;
+pub mod completions;
+pub mod install;
 pub mod install_steam_tool;
 pub mod launch;
+pub mod list_installed;
+pub mod rollback;
+pub mod serve_mirror;
+pub mod status;
+
+/// The directory name of XLCM, the deprecated compatibility tool XLM replaces.
+const LEGACY_COMPATDIR_DIRNAME: &str = "XLCM";
+
+/// Whether operations that modify anything should only be logged, set with [`set_dry_run`].
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Only log what commands would modify, launch or remove instead of doing it. Read-only requests are still sent.
+pub fn set_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+/// Whether operations that modify anything should only be logged as set with [`set_dry_run`].
+fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// The default XIVLauncher install directory, falling back to `$HOME/.local/share` or the temp directory
+/// when the local data directory can't be determined.
+fn default_install_directory() -> PathBuf {
+    dirs::data_local_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+        .unwrap_or_else(env::temp_dir)
+        .join("xlcore")
+}
+
+/// Common locations of Steam's 'compatibilitytools.d' folder, including the Flatpak and Snap versions of Steam.
+fn steam_compat_path_candidates() -> Vec<PathBuf> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    [
+        ".steam/steam",
+        ".local/share/Steam",
+        ".var/app/com.valvesoftware.Steam/data/Steam",
+        "snap/steam/common/.local/share/Steam",
+    ]
+    .iter()
+    .map(|steam_dir| home.join(steam_dir).join("compatibilitytools.d"))
+    .collect()
+}
+
+/// Read the directory a compatibility tool's launch script installs XIVLauncher into, if it was set up with a
+/// separate data directory.
+fn launch_script_install_directory(tool_dir: &Path) -> Option<PathBuf> {
+    let script = fs::read_to_string(tool_dir.join(XLM_LAUNCHSCRIPT_FILENAME)).ok()?;
+    let install_dir = script
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("export XLM_INSTALL_DIR="))?;
+    // Older launch scripts double-quote the directory while newer ones single-quote it, which both unquote the same.
+    let install_dir = shell_split(install_dir)?.into_iter().next()?;
+    (!install_dir.contains("$tooldir")).then(|| PathBuf::from(install_dir))
+}
+
+/// Warn if the deprecated XLCM compatibility tool is installed inside of the given 'compatibilitytools.d' folder,
+/// as Steam lists both tools and games may be set to use the wrong one.
+fn warn_if_legacy_compat_tool(steam_compat_path: &Path) {
+    let legacy_dir = steam_compat_path.join(LEGACY_COMPATDIR_DIRNAME);
+    if legacy_dir.is_dir() {
+        warn!(
+            "The deprecated XLCM compatibility tool is installed at {legacy_dir:?} alongside XLM. \
+            Steam lists both as compatibility tools, so make sure the game is set to use XLM and remove {legacy_dir:?} once you no longer need it."
+        );
+    }
+}
+
+/// Whether the given string is a valid shell environment variable name.
+fn is_valid_env_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Find an executable with the given name in the directories listed in `PATH`.
+fn find_in_path(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Split a string into words the way a POSIX shell would, handling quotes and backslash escapes but not expansions.
+///
+/// Returns `None` if a quote is left unclosed or the string ends with a backslash.
+fn shell_split(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xlm::includes::shell_quote;
+
+    fn words(words: &[&str]) -> Option<Vec<String>> {
+        Some(words.iter().map(|word| word.to_string()).collect())
+    }
+
+    #[test]
+    fn shell_split_splits_on_whitespace() {
+        assert_eq!(shell_split("  --a   b\tc\n"), words(&["--a", "b", "c"]));
+        assert_eq!(shell_split(""), words(&[]));
+    }
+
+    #[test]
+    fn shell_split_keeps_single_quoted_text_literal() {
+        assert_eq!(shell_split(r#"'a b' '$x\"'"#), words(&["a b", r#"$x\""#]));
+    }
+
+    #[test]
+    fn shell_split_unescapes_double_quoted_text() {
+        assert_eq!(
+            shell_split(r#""a \"b\" \$c \n""#),
+            words(&[r#"a "b" $c \n"#])
+        );
+    }
+
+    #[test]
+    fn shell_split_unescapes_backslashes_outside_quotes() {
+        assert_eq!(shell_split(r"a\ b c\'d"), words(&["a b", "c'd"]));
+    }
+
+    #[test]
+    fn shell_split_joins_adjacent_quoted_parts() {
+        assert_eq!(shell_split(r#"--key='a b'"c"d"#), words(&["--key=a bcd"]));
+    }
+
+    #[test]
+    fn shell_split_keeps_empty_quoted_words() {
+        assert_eq!(shell_split("'' \"\""), words(&["", ""]));
+    }
+
+    #[test]
+    fn shell_split_rejects_unclosed_quotes_and_trailing_backslash() {
+        assert_eq!(shell_split("'a"), None);
+        assert_eq!(shell_split("\"a"), None);
+        assert_eq!(shell_split(r"a\"), None);
+    }
+
+    #[test]
+    fn shell_split_reverses_shell_quote() {
+        let input = ["it's", "a \"b\"", "$HOME", ""];
+        let quoted = input.map(shell_quote).join(" ");
+        assert_eq!(shell_split(&quoted), words(&input));
+    }
+}
@@ -0,0 +1,114 @@
+use super::{default_install_directory, is_dry_run};
+use anyhow::{bail, Result};
+use clap::Parser;
+use dialoguer::Select;
+use log::info;
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
+use xlm::core::{
+    find_backups, restore_backup, InstallBackup, XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME,
+};
+
+/// Restore a backup of a previous XIVLauncher install kept when updating.
+#[derive(Debug, Clone, Parser)]
+pub struct RollbackCommand {
+    /// The location of the XIVLauncher install to restore a backup of.
+    #[clap(default_value_os_t = default_install_directory(), long = "install-directory")]
+    install_directory: PathBuf,
+
+    /// The file the installed version of XIVLauncher is recorded in, if it isn't inside of the install directory.
+    #[clap(long = "versiondata-path")]
+    versiondata_path: Option<PathBuf>,
+
+    /// The timestamp of the backup to restore. When omitted from an interactive terminal, a list of backups will
+    /// be shown to pick from.
+    #[clap(long = "to", value_name = "TIMESTAMP")]
+    to: Option<u64>,
+
+    /// Only list the available backups without restoring any of them.
+    #[clap(long = "list", conflicts_with = "to")]
+    list: bool,
+}
+
+impl RollbackCommand {
+    pub async fn run(self) -> Result<()> {
+        let backups = find_backups(&self.install_directory);
+        if backups.is_empty() {
+            bail!(
+                "There are no backups of {:?} to restore. Backups are only kept when updating with `--keep-n-backups` above 0.",
+                self.install_directory
+            );
+        }
+        if self.list {
+            for backup in &backups {
+                info!("{}", Self::describe(backup));
+            }
+            return Ok(());
+        }
+
+        let backup = match self.to {
+            Some(timestamp) => match backups
+                .iter()
+                .find(|backup| backup.timestamp == timestamp)
+            {
+                Some(backup) => backup,
+                None => bail!(
+                    "There is no backup with the timestamp {timestamp}, run with `--list` to see the available backups"
+                ),
+            },
+            None => Self::pick_backup(&backups)?,
+        };
+
+        if is_dry_run() {
+            info!(
+                "Would restore {} into {:?}",
+                Self::describe(backup),
+                self.install_directory
+            );
+            return Ok(());
+        }
+        info!("Restoring {}", Self::describe(backup));
+        restore_backup(
+            &self.install_directory,
+            backup,
+            self.versiondata_path.as_deref(),
+        )?;
+        info!(
+            "Restored the backup into {:?}. XLM will update it again on the next launch unless `--skip-update` is used.",
+            self.install_directory
+        );
+        Ok(())
+    }
+
+    /// Describe a backup by its timestamp and the XIVLauncher version it contains.
+    fn describe(backup: &InstallBackup) -> String {
+        let version = fs::read_to_string(backup.path.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME))
+            .map(|version| version.trim().to_string())
+            .unwrap_or_else(|_| String::from("unknown version"));
+        format!(
+            "backup {} of XIVLauncher {version} at {:?}",
+            backup.timestamp, backup.path
+        )
+    }
+
+    /// Ask the user to pick a backup to restore, newest first.
+    fn pick_backup(backups: &[InstallBackup]) -> Result<&InstallBackup> {
+        if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+            bail!("`--to` is required when not running from an interactive terminal, run with `--list` to see the available backups");
+        }
+        let newest_first = backups.iter().rev().collect::<Vec<_>>();
+        let items = newest_first
+            .iter()
+            .map(|backup| Self::describe(backup))
+            .collect::<Vec<_>>();
+        let selection = Select::new()
+            .with_prompt("Select the backup to restore")
+            .items(&items)
+            .default(0)
+            .interact()?;
+        Ok(newest_first[selection])
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+use log::{debug, info, warn};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// The largest request head that will be read before the request is rejected.
+const MAX_REQUEST_HEAD_LEN: usize = 8 * 1024;
+
+/// Serve a directory over HTTP on localhost, to test installing XIVLauncher from a web release url base.
+///
+/// The directory should contain a `version` file and the release tarball, and optionally a `manifest.sha256` file.
+#[derive(Debug, Clone, Parser)]
+pub struct ServeMirrorCommand {
+    /// The directory to serve.
+    #[clap(long = "dir")]
+    dir: PathBuf,
+
+    /// The port to listen on.
+    #[clap(long = "port", default_value_t = 8080)]
+    port: u16,
+}
+
+impl ServeMirrorCommand {
+    pub async fn run(self) -> Result<()> {
+        if !self.dir.is_dir() {
+            bail!("{:?} is not a directory", self.dir);
+        }
+        let listener = TcpListener::bind(("127.0.0.1", self.port)).await?;
+        info!(
+            "Serving {:?} at http://localhost:{}/ - use this as `--xlcore-web-release-url-base` to install from it",
+            self.dir, self.port
+        );
+        Self::serve(listener, self.dir).await
+    }
+
+    /// Respond to requests accepted by the given listener with files from the given directory.
+    async fn serve(listener: TcpListener, dir: PathBuf) -> Result<()> {
+        loop {
+            let (stream, addr) = listener.accept().await?;
+            let dir = dir.clone();
+            tokio::spawn(async move {
+                if let Err(err) = Self::handle(stream, &dir).await {
+                    warn!("Unable to respond to request from {addr}: {err:#}");
+                }
+            });
+        }
+    }
+
+    /// Respond to a single request with the requested file, closing the connection afterwards.
+    async fn handle(mut stream: TcpStream, dir: &Path) -> Result<()> {
+        let mut head = Vec::new();
+        let mut buf = [0; 1024];
+        while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut buf).await?;
+            if read == 0 || head.len() + read > MAX_REQUEST_HEAD_LEN {
+                return Self::respond(&mut stream, "400 Bad Request", None).await;
+            }
+            head.extend_from_slice(&buf[..read]);
+        }
+
+        let head = String::from_utf8_lossy(&head);
+        let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+        let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+            return Self::respond(&mut stream, "400 Bad Request", None).await;
+        };
+        debug!("{method} {target}");
+        if method != "GET" && method != "HEAD" {
+            return Self::respond(&mut stream, "405 Method Not Allowed", None).await;
+        }
+
+        let Some(path) = Self::resolve(dir, target) else {
+            return Self::respond(&mut stream, "404 Not Found", None).await;
+        };
+        match fs::read(&path) {
+            Ok(body) if method == "HEAD" => {
+                let head = Self::response_head("200 OK", body.len());
+                stream.write_all(head.as_bytes()).await?;
+                Ok(())
+            }
+            Ok(body) => Self::respond(&mut stream, "200 OK", Some(&body)).await,
+            Err(_) => Self::respond(&mut stream, "404 Not Found", None).await,
+        }
+    }
+
+    /// Resolve a request target to a file inside of the served directory, refusing anything outside of it.
+    fn resolve(dir: &Path, target: &str) -> Option<PathBuf> {
+        let target = target.split(['?', '#']).next()?;
+        let relative = Path::new(target.trim_start_matches('/'));
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        let path = dir.join(relative);
+        path.is_file().then_some(path)
+    }
+
+    fn response_head(status: &str, content_length: usize) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/octet-stream\r\nContent-Length: {content_length}\r\nConnection: close\r\n\r\n"
+        )
+    }
+
+    async fn respond(stream: &mut TcpStream, status: &str, body: Option<&[u8]>) -> Result<()> {
+        let body = body.unwrap_or(status.as_bytes());
+        stream
+            .write_all(Self::response_head(status, body.len()).as_bytes())
+            .await?;
+        stream.write_all(body).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xlm::{core::ReleaseAssetInfo, download::download_bytes};
+
+    /// A fixture directory for a test, removed when dropped so it is cleaned up even if the test fails.
+    struct FixtureDirectory(PathBuf);
+
+    impl Drop for FixtureDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_release_resolved_from_url() {
+        let directory = FixtureDirectory(
+            std::env::temp_dir().join(format!("xlm-test-{}-serve-mirror", std::process::id())),
+        );
+        fs::create_dir_all(&directory.0).unwrap();
+        fs::write(directory.0.join("version"), "1.2.3\n").unwrap();
+        fs::write(directory.0.join("XIVLauncher.Core.tar.gz"), b"release").unwrap();
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let base_url =
+            reqwest::Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(ServeMirrorCommand::serve(listener, directory.0.clone()));
+
+        let release = ReleaseAssetInfo::from_url(&base_url, "XIVLauncher.Core.tar.gz")
+            .await
+            .unwrap();
+        assert_eq!(release.version, "1.2.3");
+        assert_eq!(
+            release.url,
+            base_url.join("XIVLauncher.Core.tar.gz").unwrap()
+        );
+        assert_eq!(
+            release.manifest_url,
+            Some(base_url.join("manifest.sha256").unwrap())
+        );
+        assert_eq!(&download_bytes(release.url).await.unwrap()[..], b"release");
+    }
+
+    #[test]
+    fn resolve_refuses_paths_outside_of_directory() {
+        let dir = Path::new("/nonexistent");
+        assert_eq!(ServeMirrorCommand::resolve(dir, "/../etc/passwd"), None);
+        assert_eq!(ServeMirrorCommand::resolve(dir, "/./version"), None);
+        assert_eq!(ServeMirrorCommand::resolve(dir, "/version"), None);
+    }
+}
@@ -0,0 +1,89 @@
+use super::{steam_compat_path_candidates, warn_if_legacy_compat_tool};
+use anyhow::Result;
+use clap::Parser;
+use log::{info, warn};
+use std::{
+    collections::HashSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+use xlm::{
+    includes::{XLM_BINARY_FILENAME, XLM_COMPATDIR_DIRNAME},
+    integrity::sha256_file,
+};
+
+/// Show which version of XLM is running and whether the XLM compatibility tool is using the same binary.
+#[derive(Debug, Clone, Parser)]
+pub struct StatusCommand {
+    /// The path to the 'compatibilitytools.d' folder in your steam installation directory.
+    ///
+    /// Common Steam install locations are checked when this isn't set.
+    #[clap(long = "steam-compat-path")]
+    steam_compat_path: Option<PathBuf>,
+}
+
+impl StatusCommand {
+    pub async fn run(self) -> Result<()> {
+        let current_exe = fs::canonicalize(env::current_exe()?)?;
+        info!(
+            "XLM v{} is running from {current_exe:?}",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let compat_paths = match self.steam_compat_path {
+            Some(path) => vec![path],
+            None => steam_compat_path_candidates(),
+        };
+        // Steam's install locations are commonly symlinked to each other, so only check each binary once.
+        let mut seen = HashSet::new();
+        for path in &compat_paths {
+            if seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())) {
+                warn_if_legacy_compat_tool(path);
+            }
+        }
+        seen.clear();
+        let compat_binaries = compat_paths
+            .iter()
+            .filter_map(|path| {
+                let binary = path.join(XLM_COMPATDIR_DIRNAME).join(XLM_BINARY_FILENAME);
+                Some((path, fs::canonicalize(binary).ok()?))
+            })
+            .filter(|(_, binary)| seen.insert(binary.clone()))
+            .collect::<Vec<_>>();
+        if compat_binaries.is_empty() {
+            info!("Unable to find an XLM compatibility tool (checked {compat_paths:?})");
+            return Ok(());
+        }
+
+        let current_hash = sha256_file(&current_exe)?;
+        for (compat_path, compat_binary) in compat_binaries {
+            if compat_binary == current_exe {
+                info!("This is the XLM binary used by the compatibility tool in {compat_path:?}");
+                continue;
+            }
+            let version = Self::binary_version(&compat_binary)
+                .await
+                .unwrap_or_else(|| String::from("unknown version"));
+            if sha256_file(&compat_binary)? == current_hash {
+                info!("The compatibility tool in {compat_path:?} is using the same XLM binary ({version})");
+            } else {
+                warn!(
+                    "The compatibility tool in {compat_path:?} is using a different XLM binary ({version}). \
+                    Run `xlm install-steam-tool --steam-compat-path {compat_path:?} --update-binary-only` to update it."
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the version reported by another XLM binary.
+    async fn binary_version(path: &Path) -> Option<String> {
+        let output = Command::new(path).arg("--version").output().await.ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
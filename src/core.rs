@@ -0,0 +1,2159 @@
+//! Reusable logic for installing and updating XIVLauncher.
+//!
+//! Release information is obtained with [`ReleaseAssetInfo`] and then installed with an [`Installer`].
+
+#[cfg(feature = "test-hooks")]
+use crate::download::fault_injection;
+use crate::{
+    download::{
+        check_host_allowed, download_bytes, download_bytes_if_exists, download_streamed,
+        github_api_url, http_client, http_client_required, ip_version, user_agent, IpVersion,
+    },
+    error::XlmError,
+    includes::{ARIA2C_TARBALL_CONTENT, XLM_CACHE_MARKER_FILENAME},
+    install_manifest::{InstallAsset, InstallManifest},
+    integrity::{manifest_fingerprint, sha256_bytes, verify_sha256_manifest},
+    signature::PublicKey,
+};
+use anyhow::{bail, Context, Result};
+use bytes::{Buf, Bytes};
+use flate2::read::GzDecoder;
+use log::{debug, error, info, warn};
+use octocrab::{models::repos::Release, FromResponse};
+use reqwest::Url;
+use semver::Version;
+use std::{
+    collections::HashSet,
+    env,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Read, Write},
+    iter,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tar::{Archive, Header};
+
+pub const XIVLAUNCHER_BIN_FILENAME: &str = "XIVLauncher.Core";
+pub const ARIA2C_BIN_FILENAME: &str = "aria2c";
+pub const XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME: &str = "versiondata";
+/// How many backups of previous installs are kept by default.
+pub const DEFAULT_KEEP_BACKUPS: usize = 1;
+/// The smallest size in bytes a release archive can be before it is assumed to not be a release.
+pub const DEFAULT_MIN_RELEASE_SIZE: u64 = 512 * 1024;
+/// The GitHub API release information is requested from unless another is set with
+/// [`configure_http_client`](crate::download::configure_http_client).
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+/// Hosts GitHub release assets are downloaded from, including the hosts downloads are redirected to.
+pub const GITHUB_DOWNLOAD_HOSTS: &[&str] = &[
+    "github.com",
+    "objects.githubusercontent.com",
+    "release-assets.githubusercontent.com",
+];
+const XIVLAUNCHER_VERSION_REMOTE_FILENAME: &str = "version";
+/// The bytes every ELF binary starts with.
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const XIVLAUNCHER_MANIFEST_REMOTE_FILENAME: &str = "manifest.sha256";
+const XIVLAUNCHER_CHANGELOG_REMOTE_FILENAME: &str = "CHANGELOG";
+/// The most characters of the changelog shown alongside install progress.
+const CHANGELOG_SUMMARY_MAX_CHARS: usize = 80;
+/// Appended to the version data path to store the manifest the install was verified against.
+const XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX: &str = ".manifest";
+/// Appended to the version data path to store the version and file fingerprint of the last successful verification.
+const XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX: &str = ".verified";
+const XLM_TEMP_DIRNAME: &str = ".xlm-tmp";
+const XLM_STAGING_SUFFIX: &str = ".xlm-staging";
+const XLM_PARTIAL_SUFFIX: &str = ".xlm-partial";
+const XLM_BACKUP_INFIX: &str = ".bak.";
+const STALE_ARTIFACT_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+const ARIA2C_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+const ARIA2C_DOWNLOAD_CONNECTIONS: u8 = 8;
+const GITHUB_EMPTY_RELEASE_ATTEMPTS: u32 = 3;
+const GITHUB_EMPTY_RELEASE_RETRY_DELAY: Duration = Duration::from_secs(10);
+const GITHUB_RATE_LIMIT_ATTEMPTS: u32 = 3;
+const GITHUB_RATE_LIMIT_MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Whether release metadata obtained from GitHub is written to the debug log, set with [`dump_github_releases`].
+static DUMP_GITHUB_RELEASES: AtomicBool = AtomicBool::new(false);
+
+/// Write the metadata of every release obtained from GitHub to the debug log, to help diagnose releases with
+/// unexpected asset names or flags.
+pub fn dump_github_releases() {
+    DUMP_GITHUB_RELEASES.store(true, Ordering::Relaxed);
+}
+
+/// Where to obtain the aria2c tarball containing a static compiled 'aria2c' binary from.
+#[derive(Default, Clone, Debug)]
+pub enum AriaSource {
+    #[default]
+    Embedded,
+    Url(Url),
+    File(PathBuf),
+    /// The latest release of a GitHub repository containing an asset with the given name.
+    GitHub {
+        owner: String,
+        repo: String,
+        asset: String,
+    },
+}
+
+impl FromStr for AriaSource {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "embedded" => Ok(Self::Embedded),
+            _ if s.starts_with("url:") => Ok(Self::Url(
+                Url::parse(&s.chars().skip(4).collect::<String>()).unwrap(),
+            )),
+            _ if s.starts_with("file:") => {
+                let s = s.chars().skip(5).collect::<String>();
+                if !fs::exists(&s)
+                    .context("exists check operation failed")
+                    .unwrap()
+                {
+                    return Err("unable to find file at given path");
+                }
+                Ok(Self::File(PathBuf::from(s)))
+            }
+            _ if s.starts_with("github:") => {
+                let s = s.chars().skip(7).collect::<String>();
+                let Some((owner, repo, asset)) = s
+                    .split_once('/')
+                    .and_then(|(owner, rest)| Some((owner, rest.split_once(':')?)))
+                    .map(|(owner, (repo, asset))| (owner, repo, asset))
+                else {
+                    return Err("github sources must be in the form 'github:owner/repo:asset'");
+                };
+                if owner.is_empty() || repo.is_empty() || asset.is_empty() {
+                    return Err("github sources must be in the form 'github:owner/repo:asset'");
+                }
+                Ok(Self::GitHub {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    asset: asset.to_string(),
+                })
+            }
+            _ => Err("valid sources are 'embedded', 'url:', 'file:' or 'github:'"),
+        }
+    }
+}
+
+impl Display for AriaSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            AriaSource::Embedded => write!(f, "embedded"),
+            AriaSource::File(path) => write!(f, "file:{}", path.display()),
+            AriaSource::Url(url) => write!(f, "url:{url}"),
+            AriaSource::GitHub { owner, repo, asset } => write!(f, "github:{owner}/{repo}:{asset}"),
+        }
+    }
+}
+
+/// How the XIVLauncher release archive is downloaded.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DownloadBackend {
+    #[default]
+    Reqwest,
+    /// Download with multiple connections using aria2c.
+    Aria2c,
+}
+
+impl FromStr for DownloadBackend {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reqwest" => Ok(Self::Reqwest),
+            "aria2c" => Ok(Self::Aria2c),
+            _ => Err("valid download backends are 'reqwest' or 'aria2c'"),
+        }
+    }
+}
+
+impl Display for DownloadBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            DownloadBackend::Reqwest => write!(f, "reqwest"),
+            DownloadBackend::Aria2c => write!(f, "aria2c"),
+        }
+    }
+}
+
+/// Information about a release of XIVLauncher that can be installed.
+#[derive(Debug, Clone)]
+pub struct ReleaseAssetInfo {
+    /// The version of the release.
+    pub version: String,
+    /// The url of the release tar.gz archive.
+    pub url: Url,
+    /// The url of a SHA-256 manifest to verify extracted files against, if the source may provide one.
+    pub manifest_url: Option<Url>,
+    /// The changes made in the release, if the source provided them.
+    pub changelog: Option<String>,
+    /// The url of a file describing the changes made in the release, if the source may provide one.
+    pub changelog_url: Option<Url>,
+    /// The assets installed instead of the release archive and aria2c, when the release comes from an
+    /// [`InstallManifest`].
+    pub install_assets: Option<Vec<InstallAsset>>,
+}
+
+impl ReleaseAssetInfo {
+    /// Obtain the latest release of XIVLauncher from a GitHub repository.
+    pub async fn from_github(owner: &str, repo: &str, release_asset: &str) -> Result<Self> {
+        check_host_allowed(github_api_url())?;
+        // Releases can briefly have no assets while they are still being published, so retry a few times.
+        let mut attempt = 1;
+        let release = loop {
+            let release = Self::get_latest_github_release(owner, repo)
+                .await
+                .map_err(|source| XlmError::GitHubRelease {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    source,
+                })?;
+            #[cfg(feature = "test-hooks")]
+            let release = {
+                let mut release = release;
+                if fault_injection::take(fault_injection::EMPTY_RELEASE) {
+                    warn!("Injecting an empty release into the latest release of {owner}/{repo}");
+                    release.assets.clear();
+                }
+                release
+            };
+            if !release.assets.is_empty() || attempt >= GITHUB_EMPTY_RELEASE_ATTEMPTS {
+                break release;
+            }
+            warn!(
+                "Release {} has no assets yet and may still be publishing - retrying in {}s (attempt {attempt}/{GITHUB_EMPTY_RELEASE_ATTEMPTS})",
+                release.tag_name,
+                GITHUB_EMPTY_RELEASE_RETRY_DELAY.as_secs()
+            );
+            tokio::time::sleep(GITHUB_EMPTY_RELEASE_RETRY_DELAY).await;
+            attempt += 1;
+        };
+        if DUMP_GITHUB_RELEASES.load(Ordering::Relaxed) {
+            Self::dump_github_release(owner, repo, &release);
+        }
+
+        if release.assets.is_empty() {
+            bail!(XlmError::ReleaseHasNoAssets {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                release: release.tag_name,
+            });
+        }
+
+        let release_url = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == release_asset);
+
+        if let Some(asset) = release_url {
+            Ok(Self {
+                version: release.tag_name.trim().to_string(),
+                url: asset.browser_download_url.clone(),
+                manifest_url: None,
+                changelog: release.body.filter(|body| !body.trim().is_empty()),
+                changelog_url: None,
+                install_assets: None,
+            })
+        } else {
+            bail!(XlmError::ReleaseAssetNotFound {
+                asset: release_asset.to_string(),
+                release: release.tag_name,
+                available: release.assets.into_iter().map(|asset| asset.name).collect(),
+            });
+        }
+    }
+
+    /// Write the metadata XLM uses from a GitHub release to the debug log as JSON.
+    fn dump_github_release(owner: &str, repo: &str, release: &Release) {
+        let metadata = serde_json::json!({
+            "tag_name": release.tag_name,
+            "name": release.name,
+            "prerelease": release.prerelease,
+            "draft": release.draft,
+            "assets": release.assets.iter().map(|asset| serde_json::json!({
+                "name": asset.name,
+                "size": asset.size,
+                "browser_download_url": asset.browser_download_url,
+            })).collect::<Vec<_>>(),
+        });
+        match serde_json::to_string_pretty(&metadata) {
+            Ok(json) => debug!("Latest release of {owner}/{repo}:\n{json}"),
+            Err(err) => warn!("Unable to serialize release metadata of {owner}/{repo}: {err}"),
+        }
+    }
+
+    /// The urls requested when obtaining the latest release of a GitHub repository, including the hosts release
+    /// assets are downloaded from.
+    pub fn github_endpoints(owner: &str, repo: &str) -> Result<Vec<Url>> {
+        let mut endpoints =
+            vec![github_api_url().join(&format!("repos/{owner}/{repo}/releases/latest"))?];
+        for host in GITHUB_DOWNLOAD_HOSTS {
+            endpoints.push(Url::parse(&format!("https://{host}/"))?);
+        }
+        Ok(endpoints)
+    }
+
+    /// The urls requested when obtaining a release from a web server: the version file, the release archive, the
+    /// release manifest and the changelog.
+    pub fn url_endpoints(base_url: &Url, release_asset: &str) -> Result<Vec<Url>> {
+        let base_url = Self::directory_url(base_url);
+        Ok(vec![
+            base_url.join(XIVLAUNCHER_VERSION_REMOTE_FILENAME)?,
+            base_url.join(release_asset)?,
+            base_url.join(XIVLAUNCHER_MANIFEST_REMOTE_FILENAME)?,
+            base_url.join(XIVLAUNCHER_CHANGELOG_REMOTE_FILENAME)?,
+        ])
+    }
+
+    /// Get the latest release of a GitHub repository, waiting and retrying if GitHub responds with a secondary
+    /// rate limit that asks to be retried shortly.
+    async fn get_latest_github_release(owner: &str, repo: &str) -> octocrab::Result<Release> {
+        let octocrab = octocrab::instance();
+        let route = format!("/repos/{owner}/{repo}/releases/latest");
+        let mut attempt = 1;
+        loop {
+            let response = octocrab._get(route.as_str()).await?;
+            let status = response.status().as_u16();
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+                .map(Duration::from_secs);
+            #[cfg(feature = "test-hooks")]
+            let (status, retry_after) = match fault_injection::take(fault_injection::RATE_LIMIT) {
+                true => {
+                    warn!("Injecting a rate limit into the request for the latest release of {owner}/{repo}");
+                    (429, Some(Duration::from_secs(1)))
+                }
+                false => (status, retry_after),
+            };
+            if let (429 | 403, Some(retry_after)) = (status, retry_after) {
+                if attempt < GITHUB_RATE_LIMIT_ATTEMPTS && retry_after <= GITHUB_RATE_LIMIT_MAX_WAIT
+                {
+                    warn!(
+                        "GitHub is rate limiting requests - retrying in {}s (attempt {attempt}/{GITHUB_RATE_LIMIT_ATTEMPTS})",
+                        retry_after.as_secs()
+                    );
+                    tokio::time::sleep(retry_after).await;
+                    attempt += 1;
+                    continue;
+                }
+                warn!(
+                    "GitHub is rate limiting requests and asked to retry in {}s, giving up",
+                    retry_after.as_secs()
+                );
+            }
+            return Release::from_response(octocrab::map_github_error(response).await?).await;
+        }
+    }
+
+    /// Obtain a release of XIVLauncher made up of the assets listed in an install manifest.
+    ///
+    /// The url of the release is the url of the first asset.
+    pub fn from_install_manifest(manifest: InstallManifest) -> Self {
+        Self {
+            version: manifest.version.trim().to_string(),
+            url: manifest.assets[0].url.clone(),
+            manifest_url: None,
+            changelog: None,
+            changelog_url: None,
+            install_assets: Some(manifest.assets),
+        }
+    }
+
+    /// Ensure a web release url base ends with a slash, as joining a file name onto it would otherwise replace its
+    /// last path segment instead of requesting the file from inside of it.
+    fn directory_url(base_url: &Url) -> Url {
+        let mut base_url = base_url.clone();
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+        base_url
+    }
+
+    /// Obtain a release of XIVLauncher from a web server.
+    ///
+    /// The base url should contain a `version` file, the release archive and optionally `manifest.sha256` and
+    /// `CHANGELOG` files.
+    pub async fn from_url(base_url: &Url, release_asset: &str) -> Result<Self> {
+        let normalized_base_url = Self::directory_url(base_url);
+        if normalized_base_url != *base_url {
+            warn!("Web release url base {base_url} does not end with a slash, using {normalized_base_url} so files are requested from inside of it");
+        }
+        let base_url = &normalized_base_url;
+        let version_url = base_url.join(XIVLAUNCHER_VERSION_REMOTE_FILENAME)?;
+        let release_url = base_url.join(release_asset)?;
+
+        check_host_allowed(&version_url)?;
+        info!("XIVLauncher web release asset url:{}", release_url);
+        info!("XIVLauncher web release version url: {}", version_url);
+
+        let version = async {
+            let request = http_client().get(version_url.clone());
+            #[cfg(feature = "test-hooks")]
+            let request = fault_injection::apply(request, &version_url);
+            request.send().await?.error_for_status()?.text().await
+        }
+        .await
+        .map_err(|err| XlmError::network(version_url, err))?;
+        Ok(Self {
+            // Servers commonly end the version file with a newline.
+            version: version.trim().to_string(),
+            url: release_url,
+            manifest_url: Some(base_url.join(XIVLAUNCHER_MANIFEST_REMOTE_FILENAME)?),
+            changelog: None,
+            changelog_url: Some(base_url.join(XIVLAUNCHER_CHANGELOG_REMOTE_FILENAME)?),
+            install_assets: None,
+        })
+    }
+}
+
+/// The directory release archives are staged in for the given install directory when no temp directory is set.
+pub fn default_temp_directory(install_directory: &Path) -> PathBuf {
+    install_parent_directory(install_directory).join(XLM_TEMP_DIRNAME)
+}
+
+fn install_parent_directory(install_directory: &Path) -> &Path {
+    match install_directory.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// A backup of a previous install, kept when an update replaced it.
+#[derive(Debug, Clone)]
+pub struct InstallBackup {
+    /// When the backup was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The directory containing the backed up install.
+    pub path: PathBuf,
+}
+
+/// Find the backups of previous installs of the given install directory, oldest first.
+///
+/// Backups are kept next to the install directory and named `<install directory>.bak.<timestamp>`.
+pub fn find_backups(install_directory: &Path) -> Vec<InstallBackup> {
+    let Some(name) = install_directory.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{name}{XLM_BACKUP_INFIX}");
+    let Ok(entries) = fs::read_dir(install_parent_directory(install_directory)) else {
+        return Vec::new();
+    };
+    let mut backups = entries
+        .flatten()
+        .filter_map(|entry| {
+            let timestamp = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix(&prefix)?
+                .parse()
+                .ok()?;
+            entry.file_type().ok()?.is_dir().then(|| InstallBackup {
+                timestamp,
+                path: entry.path(),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|backup| backup.timestamp);
+    backups
+}
+
+/// Swap a backup of a previous install back into place of the install directory, restoring the version it
+/// recorded to the given version data path if it is stored outside of the install directory.
+///
+/// The backup must contain the XIVLauncher binary. The replaced install is removed.
+pub fn restore_backup(
+    install_directory: &Path,
+    backup: &InstallBackup,
+    versiondata_path: Option<&Path>,
+) -> Result<()> {
+    if !fs::exists(backup.path.join(XIVLAUNCHER_BIN_FILENAME))? {
+        bail!(
+            "Backup {:?} does not contain {XIVLAUNCHER_BIN_FILENAME} and can't be restored",
+            backup.path
+        );
+    }
+
+    // Move the current install aside first so it can be put back if the backup can't be moved into place.
+    let mut replaced_name = install_directory
+        .file_name()
+        .context("install directory has no name")?
+        .to_owned();
+    replaced_name.push(XLM_STAGING_SUFFIX);
+    let replaced_path = install_parent_directory(install_directory).join(replaced_name);
+    let _ = fs::remove_dir_all(&replaced_path);
+    let had_install = fs::exists(install_directory)?;
+    if had_install {
+        fs::rename(install_directory, &replaced_path).map_err(|source| {
+            XlmError::InstallDirNotWritable {
+                path: install_directory.to_path_buf(),
+                source,
+            }
+        })?;
+    }
+    if let Err(err) = fs::rename(&backup.path, install_directory) {
+        if had_install {
+            let _ = fs::rename(&replaced_path, install_directory);
+        }
+        return Err(err)
+            .with_context(|| format!("unable to move backup {:?} into place", backup.path));
+    }
+    if had_install {
+        let _ = fs::remove_dir_all(&replaced_path);
+    }
+
+    if let Some(versiondata_path) = versiondata_path {
+        let backup_versiondata_path =
+            install_directory.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME);
+        fs::copy(&backup_versiondata_path, versiondata_path)
+            .with_context(|| format!("unable to restore version data to {versiondata_path:?}"))?;
+        let manifest_path = sidecar_path(versiondata_path, XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX);
+        let _ = fs::remove_file(&manifest_path);
+        let _ = fs::copy(
+            sidecar_path(&backup_versiondata_path, XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX),
+            &manifest_path,
+        );
+        let _ = fs::remove_file(sidecar_path(
+            versiondata_path,
+            XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX,
+        ));
+    }
+    Ok(())
+}
+
+/// The path of a file stored next to the version data, named by appending a suffix to it.
+fn sidecar_path(versiondata_path: &Path, suffix: &str) -> PathBuf {
+    let mut path = versiondata_path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Verify the install against the manifest recorded when it was installed, if there is one.
+///
+/// Hashing is skipped when the install was already verified for the same version and none of its files have
+/// changed size or modification time since, unless `force` is set. Returns whether the files were hashed.
+pub fn verify_installed_files(
+    install_directory: &Path,
+    versiondata_path: &Path,
+    version: &str,
+    force: bool,
+) -> Result<bool> {
+    let Ok(manifest) = fs::read_to_string(sidecar_path(
+        versiondata_path,
+        XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX,
+    )) else {
+        debug!("No release manifest was recorded for the install, skipping file verification");
+        return Ok(false);
+    };
+    let verified_path = sidecar_path(versiondata_path, XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX);
+    let verified = format!(
+        "{version}\n{}",
+        manifest_fingerprint(install_directory, &manifest)?
+    );
+    if !force && fs::read_to_string(&verified_path).is_ok_and(|cached| cached == verified) {
+        debug!("XIVLauncher files are unchanged since they were last verified, skipping file verification");
+        return Ok(false);
+    }
+
+    verify_sha256_manifest(install_directory, &manifest)?;
+    fs::write(&verified_path, verified)
+        .with_context(|| format!("unable to record verification at {verified_path:?}"))?;
+    Ok(true)
+}
+
+/// Record the manifest an install was verified against along with its current file fingerprint, so later
+/// verifications can be skipped until the files change.
+fn record_verified_install(
+    install_directory: &Path,
+    versiondata_path: &Path,
+    version: &str,
+    manifest: &str,
+) -> Result<()> {
+    if let Some(parent) = versiondata_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        sidecar_path(versiondata_path, XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX),
+        manifest,
+    )?;
+    fs::write(
+        sidecar_path(versiondata_path, XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX),
+        format!(
+            "{version}\n{}",
+            manifest_fingerprint(install_directory, manifest)?
+        ),
+    )?;
+    Ok(())
+}
+
+/// Remove staging directories and partially written downloads left inside of the given directories by
+/// interrupted installs, returning the paths that were removed.
+///
+/// Only entries named with XLM's staging or partial suffixes that haven't been modified for an hour are removed,
+/// so installs running at the same time are left alone.
+pub fn remove_stale_artifacts(directories: &[&Path]) -> Vec<PathBuf> {
+    let mut removed = Vec::new();
+    for directory in directories {
+        let Ok(entries) = fs::read_dir(directory) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !name.ends_with(XLM_STAGING_SUFFIX) && !name.ends_with(XLM_PARTIAL_SUFFIX) {
+                continue;
+            }
+            let is_stale = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= STALE_ARTIFACT_MIN_AGE);
+            if !is_stale {
+                continue;
+            }
+            let path = entry.path();
+            let result = match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => fs::remove_dir_all(&path),
+                _ => fs::remove_file(&path),
+            };
+            match result {
+                Ok(()) => removed.push(path),
+                Err(err) => warn!("Unable to remove stale file {path:?}: {err}"),
+            }
+        }
+    }
+    removed
+}
+
+/// Remove everything inside of XLM's default temp directory next to the install directory that hasn't been modified
+/// for at least `min_age`, along with the directory itself once it is empty, returning the paths that were removed.
+///
+/// Only the default temp directory is cleaned as everything inside of it was created by XLM.
+pub fn remove_old_temp_files(install_directory: &Path, min_age: Duration) -> Vec<PathBuf> {
+    let temp_directory = default_temp_directory(install_directory);
+    let Ok(entries) = fs::read_dir(&temp_directory) else {
+        return Vec::new();
+    };
+    let mut removed = Vec::new();
+    for entry in entries.flatten() {
+        let is_old = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age >= min_age);
+        if !is_old {
+            continue;
+        }
+        let path = entry.path();
+        let result = match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => fs::remove_dir_all(&path),
+            _ => fs::remove_file(&path),
+        };
+        match result {
+            Ok(()) => removed.push(path),
+            Err(err) => warn!("Unable to remove old temp file {path:?}: {err}"),
+        }
+    }
+    if fs::remove_dir(&temp_directory).is_ok() {
+        removed.push(temp_directory);
+    }
+    removed
+}
+
+/// Whether an install of the local version should be updated to the remote version.
+///
+/// Versions are compared as semver, ignoring surrounding whitespace and a leading `v`, so that only a newer remote
+/// version is installed. Versions that aren't valid semver are instead updated whenever they differ.
+pub fn is_update_needed(local_version: &str, remote_version: &str) -> bool {
+    let parse = |version: &str| Version::parse(version.trim().trim_start_matches(['v', 'V'])).ok();
+    match (parse(local_version), parse(remote_version)) {
+        (Some(local), Some(remote)) => remote > local,
+        _ => local_version.trim() != remote_version.trim(),
+    }
+}
+
+/// A phase of an install, reported alongside each step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallPhase {
+    Downloading,
+    Verifying,
+    Extracting,
+    Finishing,
+}
+
+impl Display for InstallPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            InstallPhase::Downloading => write!(f, "downloading"),
+            InstallPhase::Verifying => write!(f, "verifying"),
+            InstallPhase::Extracting => write!(f, "extracting"),
+            InstallPhase::Finishing => write!(f, "finishing"),
+        }
+    }
+}
+
+/// A step of an install reported to the progress callback.
+#[derive(Debug, Clone, Copy)]
+pub struct InstallProgress<'a> {
+    /// The phase the install is currently in.
+    pub phase: InstallPhase,
+    /// An estimate of how far through the install is, from 0 to 100.
+    pub percent: u8,
+    /// A short description of the step.
+    pub text: &'a str,
+}
+
+/// Callback invoked with each step of an install.
+pub type ProgressCallback = Box<dyn Fn(&InstallProgress) + Send + Sync>;
+
+/// Installs a release of XIVLauncher, created with [`Installer::builder`].
+pub struct Installer {
+    release: ReleaseAssetInfo,
+    install_directory: PathBuf,
+    aria_source: AriaSource,
+    aria_binary_name: String,
+    download_backend: DownloadBackend,
+    min_release_size: u64,
+    keep_backups: usize,
+    log_extracted_files: bool,
+    skip_aria_validation: bool,
+    signature: Option<(Url, PublicKey)>,
+    incremental_update: bool,
+    extract_only: Vec<String>,
+    stream_extract: bool,
+    temp_directory: Option<PathBuf>,
+    download_cache: Option<PathBuf>,
+    versiondata_path: Option<PathBuf>,
+    on_progress: Option<ProgressCallback>,
+}
+
+/// Builder for an [`Installer`].
+pub struct InstallerBuilder {
+    installer: Installer,
+}
+
+impl InstallerBuilder {
+    /// Set where to obtain aria2c from. Defaults to the tarball embedded in XLM.
+    pub fn aria_source(mut self, aria_source: AriaSource) -> Self {
+        self.installer.aria_source = aria_source;
+        self
+    }
+
+    /// Set the name of the binary inside of the aria2c tarball. Defaults to `aria2c`.
+    pub fn aria_binary_name(mut self, aria_binary_name: impl Into<String>) -> Self {
+        self.installer.aria_binary_name = aria_binary_name.into();
+        self
+    }
+
+    /// Set how the release archive is downloaded. Defaults to [`DownloadBackend::Reqwest`].
+    pub fn download_backend(mut self, download_backend: DownloadBackend) -> Self {
+        self.installer.download_backend = download_backend;
+        self
+    }
+
+    /// Set the smallest size in bytes the release archive can be, below which it is rejected as it is likely an
+    /// error page. Defaults to [`DEFAULT_MIN_RELEASE_SIZE`].
+    pub fn min_release_size(mut self, min_release_size: u64) -> Self {
+        self.installer.min_release_size = min_release_size;
+        self
+    }
+
+    /// Set how many backups of previous installs to keep when an update replaces the install directory, removing
+    /// the oldest backups beyond this. Defaults to [`DEFAULT_KEEP_BACKUPS`], and `0` disables backups.
+    pub fn keep_backups(mut self, keep_backups: usize) -> Self {
+        self.installer.keep_backups = keep_backups;
+        self
+    }
+
+    /// Log the path and size of every file extracted from the release archive at the debug level, to help diagnose
+    /// archives with an unexpected layout.
+    pub fn log_extracted_files(mut self, log_extracted_files: bool) -> Self {
+        self.installer.log_extracted_files = log_extracted_files;
+        self
+    }
+
+    /// Only warn instead of failing when the aria2c binary is missing after installing, and don't check that it
+    /// can run, for setups that provide aria2c some other way.
+    pub fn skip_aria_validation(mut self, skip_aria_validation: bool) -> Self {
+        self.installer.skip_aria_validation = skip_aria_validation;
+        self
+    }
+
+    /// Verify the release archive against a detached signature before extracting it.
+    pub fn signature(mut self, signature_url: Url, public_key: PublicKey) -> Self {
+        self.installer.signature = Some((signature_url, public_key));
+        self
+    }
+
+    /// Only extract files that differ from an existing install instead of replacing the whole install directory.
+    ///
+    /// Falls back to a full extraction when there is no existing install or the archive can't be compared against it.
+    pub fn incremental_update(mut self, incremental_update: bool) -> Self {
+        self.installer.incremental_update = incremental_update;
+        self
+    }
+
+    /// Only extract the entries of the release archive whose path matches one of the given patterns over an
+    /// existing install, leaving every other installed file as it is. Patterns may contain `*` to match any run of
+    /// characters, including `/`, and `?` to match any single character.
+    ///
+    /// Falls back to a full extraction when there is no existing install. Extracts everything by default.
+    pub fn extract_only(mut self, patterns: Vec<String>) -> Self {
+        self.installer.extract_only = patterns;
+        self
+    }
+
+    /// Extract the release archive while it is downloaded instead of after, which requires a multi-threaded tokio
+    /// runtime.
+    ///
+    /// Falls back to downloading the whole archive first when it must be verified against a signature, is
+    /// downloaded with aria2c, is already in the download cache or is compared against an existing install.
+    pub fn stream_extract(mut self, stream_extract: bool) -> Self {
+        self.installer.stream_extract = stream_extract;
+        self
+    }
+
+    /// Set the directory release archives are staged in before being moved into the install directory.
+    ///
+    /// Defaults to a directory next to the install directory. This should be on the same filesystem as the install
+    /// directory so the staged files can be renamed into place.
+    pub fn temp_directory(mut self, temp_directory: impl Into<PathBuf>) -> Self {
+        self.installer.temp_directory = Some(temp_directory.into());
+        self
+    }
+
+    /// Store downloaded release archives inside of the given directory, keyed by version, and reuse them for later
+    /// installs of the same version instead of downloading them again.
+    pub fn download_cache(mut self, download_cache: impl Into<PathBuf>) -> Self {
+        self.installer.download_cache = Some(download_cache.into());
+        self
+    }
+
+    /// Set where the installed version is recorded. Defaults to a `versiondata` file inside of the install directory.
+    pub fn versiondata_path(mut self, versiondata_path: impl Into<PathBuf>) -> Self {
+        self.installer.versiondata_path = Some(versiondata_path.into());
+        self
+    }
+
+    /// Set a callback to receive each step of the install.
+    pub fn on_progress(
+        mut self,
+        on_progress: impl Fn(&InstallProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.installer.on_progress = Some(Box::new(on_progress));
+        self
+    }
+
+    pub fn build(self) -> Installer {
+        self.installer
+    }
+}
+
+impl Installer {
+    /// Create a builder to install the given release into the given directory.
+    pub fn builder(
+        release: ReleaseAssetInfo,
+        install_directory: impl Into<PathBuf>,
+    ) -> InstallerBuilder {
+        InstallerBuilder {
+            installer: Self {
+                release,
+                install_directory: install_directory.into(),
+                aria_source: AriaSource::default(),
+                aria_binary_name: String::from(ARIA2C_BIN_FILENAME),
+                download_backend: DownloadBackend::default(),
+                min_release_size: DEFAULT_MIN_RELEASE_SIZE,
+                keep_backups: DEFAULT_KEEP_BACKUPS,
+                log_extracted_files: false,
+                skip_aria_validation: false,
+                signature: None,
+                incremental_update: false,
+                extract_only: Vec::new(),
+                stream_extract: false,
+                temp_directory: None,
+                download_cache: None,
+                versiondata_path: None,
+                on_progress: None,
+            },
+        }
+    }
+
+    /// The path of the file the installed version is recorded in.
+    fn versiondata_path(&self) -> PathBuf {
+        self.versiondata_path.clone().unwrap_or_else(|| {
+            self.install_directory
+                .join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME)
+        })
+    }
+
+    fn set_progress(&self, phase: InstallPhase, percent: u8, text: &str) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&InstallProgress {
+                phase,
+                percent,
+                text,
+            });
+        }
+    }
+
+    /// The path the release archive is stored at inside of the download cache, if one is set.
+    fn cached_release_path(&self) -> Option<PathBuf> {
+        let asset_name = self
+            .release
+            .url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .unwrap_or("release.tar.gz");
+        let version = self.release.version.trim().replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '-',
+            "_",
+        );
+        Some(
+            self.download_cache
+                .as_ref()?
+                .join(format!("{version}-{asset_name}")),
+        )
+    }
+
+    /// Read the release archive from the download cache, ignoring it if it doesn't match its stored checksum.
+    fn read_cached_release(&self) -> Option<Bytes> {
+        let path = self.cached_release_path()?;
+        let checksum_path = cached_checksum_path(&path);
+        let (Ok(bytes), Ok(checksum)) = (fs::read(&path), fs::read_to_string(&checksum_path))
+        else {
+            debug!(
+                "XIVLauncher release {} is not in the download cache",
+                self.release.version
+            );
+            return None;
+        };
+        if sha256_bytes(&bytes) != checksum.trim() {
+            warn!("Cached XIVLauncher release at {path:?} does not match its checksum - downloading it again");
+            let _ = fs::remove_file(&path);
+            let _ = fs::remove_file(&checksum_path);
+            return None;
+        }
+        info!("Using cached XIVLauncher release from {path:?}");
+        Some(Bytes::from(bytes))
+    }
+
+    /// Store the release archive and its checksum inside of the download cache.
+    fn write_cached_release(&self, bytes: &[u8]) -> Result<()> {
+        let Some(path) = self.cached_release_path() else {
+            return Ok(());
+        };
+        let cache_directory = path.parent().context("download cache has no directory")?;
+        fs::create_dir_all(cache_directory)?;
+        File::create(cache_directory.join(XLM_CACHE_MARKER_FILENAME))?;
+        // Write to a partial file first so an interrupted write is never mistaken for a cached release.
+        let mut partial_path = path.as_os_str().to_owned();
+        partial_path.push(XLM_PARTIAL_SUFFIX);
+        fs::write(&partial_path, bytes)?;
+        fs::rename(&partial_path, &path)?;
+        fs::write(cached_checksum_path(&path), sha256_bytes(bytes))?;
+        info!(
+            "Added XIVLauncher release {} to the download cache at {path:?}",
+            self.release.version
+        );
+        Ok(())
+    }
+
+    /// Download the release archive into the temp directory with aria2c using multiple connections.
+    ///
+    /// Uses the aria2c binary inside of the install directory if there is one, otherwise the embedded aria2c
+    /// binary is extracted into the temp directory for the download.
+    async fn download_with_aria2c(&self) -> Result<Bytes> {
+        let url = &self.release.url;
+        check_host_allowed(url)?;
+        let temp_directory = self
+            .temp_directory
+            .clone()
+            .unwrap_or_else(|| default_temp_directory(&self.install_directory));
+        fs::create_dir_all(&temp_directory).map_err(|source| XlmError::TempDirNotWritable {
+            path: temp_directory.clone(),
+            source,
+        })?;
+
+        let installed_aria2c = self.install_directory.join(&self.aria_binary_name);
+        let (aria2c, extracted) = if fs::exists(&installed_aria2c)? {
+            (installed_aria2c, false)
+        } else {
+            debug!("Extracting embedded aria2c into {temp_directory:?} to download with");
+            Archive::new(GzDecoder::new(ARIA2C_TARBALL_CONTENT)).unpack(&temp_directory)?;
+            let aria2c = temp_directory.join(ARIA2C_BIN_FILENAME);
+            ensure_executable(&aria2c)?;
+            (aria2c, true)
+        };
+
+        let output_name = format!("release.tar.gz{XLM_PARTIAL_SUFFIX}");
+        let output_path = temp_directory.join(&output_name);
+        info!("Downloading {url} with aria2c using {ARIA2C_DOWNLOAD_CONNECTIONS} connections");
+        let mut command = tokio::process::Command::new(&aria2c);
+        if ip_version() == IpVersion::V4 {
+            command.arg("--disable-ipv6=true");
+        }
+        let status = command
+            .arg(format!("--dir={}", temp_directory.display()))
+            .arg(format!("--out={output_name}"))
+            .arg(format!("--split={ARIA2C_DOWNLOAD_CONNECTIONS}"))
+            .arg(format!(
+                "--max-connection-per-server={ARIA2C_DOWNLOAD_CONNECTIONS}"
+            ))
+            .arg(format!("--user-agent={}", user_agent()))
+            .args([
+                "--allow-overwrite=true",
+                "--auto-file-renaming=false",
+                "--console-log-level=warn",
+                "--summary-interval=0",
+            ])
+            .arg(url.as_str())
+            .kill_on_drop(true)
+            .status()
+            .await;
+        if extracted {
+            let _ = fs::remove_file(&aria2c);
+        }
+        let result = match status {
+            Ok(status) if status.success() => fs::read(&output_path)
+                .map(Bytes::from)
+                .with_context(|| format!("unable to read {output_path:?} downloaded by aria2c")),
+            Ok(status) => Err(anyhow::anyhow!(
+                "aria2c failed to download {url} ({status})"
+            )),
+            Err(err) => Err(err).context("unable to run aria2c"),
+        };
+        let _ = fs::remove_file(&output_path);
+        let mut control_path = output_path.into_os_string();
+        control_path.push(".aria2");
+        let _ = fs::remove_file(control_path);
+        result
+    }
+
+    /// Move the existing install out of the way, keeping it as a timestamped backup when backups are enabled and
+    /// removing the oldest backups beyond the number to keep.
+    fn remove_existing_install(&self) -> Result<()> {
+        let install_location = &self.install_directory;
+        if !fs::exists(install_location)? {
+            return Ok(());
+        }
+        if self.keep_backups == 0 {
+            let _ = fs::remove_dir_all(install_location);
+            return Ok(());
+        }
+
+        let name = install_location
+            .file_name()
+            .and_then(|name| name.to_str())
+            .context("install directory has no valid name")?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let backup_path = install_parent_directory(install_location)
+            .join(format!("{name}{XLM_BACKUP_INFIX}{timestamp}"));
+        info!("Backing up the existing XIVLauncher install to {backup_path:?}");
+        if let Err(err) = fs::rename(install_location, &backup_path) {
+            warn!(
+                "Unable to back up the existing XIVLauncher install, removing it instead: {err:?}"
+            );
+            let _ = fs::remove_dir_all(install_location);
+            return Ok(());
+        }
+        // Keep the backed up version with the backup when version data is stored elsewhere.
+        if self.versiondata_path.is_some() {
+            let backup_versiondata_path = backup_path.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME);
+            if let Err(err) = fs::copy(self.versiondata_path(), &backup_versiondata_path) {
+                warn!("Unable to copy version data into the backup at {backup_path:?}: {err:?}");
+            }
+            let _ = fs::copy(
+                sidecar_path(&self.versiondata_path(), XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX),
+                sidecar_path(&backup_versiondata_path, XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX),
+            );
+        }
+
+        let backups = find_backups(install_location);
+        let excess = backups.len().saturating_sub(self.keep_backups);
+        for backup in &backups[..excess] {
+            info!("Removing old XIVLauncher backup {:?}", backup.path);
+            if let Err(err) = fs::remove_dir_all(&backup.path) {
+                warn!("Unable to remove old backup {:?}: {err:?}", backup.path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract a release into a staging directory with the given function and then move it into place, so a failed
+    /// extraction doesn't leave a partial install behind.
+    ///
+    /// Extracts directly into the install directory if the staging directory is on a different filesystem.
+    fn unpack_staged(&self, unpack: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+        let install_location = &self.install_directory;
+        let install_parent = install_parent_directory(install_location);
+        fs::create_dir_all(install_parent).map_err(|source| XlmError::InstallDirNotWritable {
+            path: install_parent.to_path_buf(),
+            source,
+        })?;
+        let temp_directory = self
+            .temp_directory
+            .clone()
+            .unwrap_or_else(|| default_temp_directory(install_location));
+        fs::create_dir_all(&temp_directory).map_err(|source| XlmError::TempDirNotWritable {
+            path: temp_directory.clone(),
+            source,
+        })?;
+
+        if fs::metadata(&temp_directory)?.dev() != fs::metadata(install_parent)?.dev() {
+            warn!("Temp directory {temp_directory:?} is not on the same filesystem as {install_location:?} - extracting directly into the install directory");
+            self.remove_existing_install()?;
+            fs::create_dir_all(install_location).map_err(|source| {
+                XlmError::InstallDirNotWritable {
+                    path: install_location.clone(),
+                    source,
+                }
+            })?;
+            info!("Unpacking XIVLauncher release tarball");
+            unpack(install_location)?;
+            return unwrap_release_directory(install_location);
+        }
+
+        let mut staging_name = install_location
+            .file_name()
+            .context("install directory has no name")?
+            .to_owned();
+        staging_name.push(XLM_STAGING_SUFFIX);
+        let staging_directory = temp_directory.join(staging_name);
+        let _ = fs::remove_dir_all(&staging_directory);
+        fs::create_dir_all(&staging_directory).map_err(|source| XlmError::TempDirNotWritable {
+            path: staging_directory.clone(),
+            source,
+        })?;
+        info!("Unpacking XIVLauncher release tarball into {staging_directory:?}");
+        if let Err(err) =
+            unpack(&staging_directory).and_then(|_| unwrap_release_directory(&staging_directory))
+        {
+            let _ = fs::remove_dir_all(&staging_directory);
+            return Err(err);
+        }
+
+        debug!("Moving staged XIVLauncher files into {install_location:?}");
+        self.remove_existing_install()?;
+        fs::rename(&staging_directory, install_location).map_err(|source| {
+            XlmError::InstallDirNotWritable {
+                path: install_location.clone(),
+                source,
+            }
+        })?;
+        // Only remove the default temp directory, which may be left empty.
+        if self.temp_directory.is_none() {
+            let _ = fs::remove_dir(&temp_directory);
+        }
+        Ok(())
+    }
+
+    /// Extract the release archive into the install directory, only extracting changed or matching files over an
+    /// existing install when enabled.
+    fn unpack_release(&self, bytes: &Bytes) -> Result<()> {
+        let install_location = &self.install_directory;
+        self.set_progress(InstallPhase::Extracting, 35, "Extracting XIVLauncher");
+        let has_existing_install = fs::exists(self.versiondata_path())?;
+        let unpacked_in_place = if !self.extract_only.is_empty() && has_existing_install {
+            unpack_matching(
+                bytes,
+                install_location,
+                &self.extract_only,
+                self.log_extracted_files,
+            )?;
+            true
+        } else {
+            if !self.extract_only.is_empty() {
+                warn!("Not limiting extraction to the given patterns as XIVLauncher isn't installed yet - extracting everything");
+            }
+            self.incremental_update
+                && has_existing_install
+                && match unpack_incremental(bytes, install_location, self.log_extracted_files) {
+                    Ok(true) => true,
+                    Ok(false) => {
+                        info!("XIVLauncher release tarball can't be compared against the existing install - falling back to a full extraction");
+                        false
+                    }
+                    Err(err) => {
+                        warn!("Incremental update failed - falling back to a full extraction: {err:?}");
+                        false
+                    }
+                }
+        };
+        if !unpacked_in_place {
+            self.unpack_staged(|directory| {
+                unpack_archive(bytes.clone().reader(), directory, self.log_extracted_files)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The changes made in the release being installed, fetching them from the release's changelog url if needed.
+    async fn changelog(&self) -> Option<String> {
+        if let Some(changelog) = &self.release.changelog {
+            return Some(changelog.clone());
+        }
+        let changelog_url = self.release.changelog_url.as_ref()?;
+        match download_bytes_if_exists(changelog_url.clone()).await {
+            Ok(Some(changelog)) => Some(String::from_utf8_lossy(&changelog).into_owned())
+                .filter(|changelog| !changelog.trim().is_empty()),
+            Ok(None) => {
+                debug!("No changelog available at {changelog_url}");
+                None
+            }
+            Err(err) => {
+                debug!("Unable to download the changelog from {changelog_url}: {err:?}");
+                None
+            }
+        }
+    }
+
+    /// Whether the release archive can be extracted while it is downloaded, which is only possible when it doesn't
+    /// need to be fully downloaded first to verify it or compare it against an existing install.
+    fn can_stream_extract(&self) -> Result<bool> {
+        if !self.stream_extract {
+            return Ok(false);
+        }
+        let reason = if self.signature.is_some() {
+            "its signature must be verified before extracting it"
+        } else if self.download_backend != DownloadBackend::Reqwest {
+            "it is downloaded with aria2c"
+        } else if (self.incremental_update || !self.extract_only.is_empty())
+            && fs::exists(self.versiondata_path())?
+        {
+            "it is being compared against the existing install"
+        } else {
+            return Ok(true);
+        };
+        info!("Not extracting the XIVLauncher release while it downloads as {reason}");
+        Ok(false)
+    }
+
+    /// Download the release archive while extracting it as it arrives, returning the full archive once both have
+    /// finished.
+    async fn download_and_unpack_streamed(&self) -> Result<Bytes> {
+        info!("Extracting XIVLauncher release while it downloads");
+        let (mut reader, download) = download_streamed(self.release.url.clone()).await?;
+        self.set_progress(
+            InstallPhase::Extracting,
+            0,
+            "Downloading and extracting XIVLauncher",
+        );
+        let mut downloaded = None;
+        // Extraction blocks on the download, so it must run outside of the async runtime's task.
+        tokio::task::block_in_place(|| {
+            self.unpack_staged(|directory| {
+                unpack_archive(&mut reader, directory, self.log_extracted_files)?;
+                // Read anything left after the end of the archive so the whole download is checked.
+                io::copy(&mut reader, &mut io::sink())?;
+                let bytes = tokio::runtime::Handle::current().block_on(download)??;
+                if (bytes.len() as u64) < self.min_release_size {
+                    bail!(XlmError::ReleaseTooSmall {
+                        url: self.release.url.clone(),
+                        size: bytes.len() as u64,
+                        min_size: self.min_release_size,
+                    });
+                }
+                downloaded = Some(bytes);
+                Ok(())
+            })
+        })?;
+        downloaded.context("streamed download did not finish")
+    }
+
+    /// Download and verify every asset of an install manifest, then extract or copy them into the install directory
+    /// in order.
+    async fn install_assets(&self, assets: &[InstallAsset]) -> Result<()> {
+        // Version data is only written once an install has finished, so it marks an existing install.
+        let action = install_action(&self.release.version, fs::exists(self.versiondata_path())?);
+        info!("{action}");
+        self.set_progress(InstallPhase::Downloading, 0, &action);
+
+        let mut downloaded = Vec::with_capacity(assets.len());
+        for (index, asset) in assets.iter().enumerate() {
+            info!("Downloading {} from {}", asset.filename, asset.url);
+            self.set_progress(
+                InstallPhase::Downloading,
+                (index * 50 / assets.len()) as u8,
+                &format!("Downloading {}", asset.filename),
+            );
+            let bytes = download_bytes(asset.url.clone()).await?;
+            if let Some(expected) = &asset.sha256 {
+                let actual = sha256_bytes(&bytes);
+                if !actual.eq_ignore_ascii_case(expected) {
+                    bail!(XlmError::ChecksumMismatch {
+                        path: PathBuf::from(&asset.filename),
+                        expected: expected.clone(),
+                        actual,
+                    });
+                }
+            }
+            downloaded.push((asset, bytes));
+        }
+
+        self.set_progress(InstallPhase::Extracting, 50, "Extracting XIVLauncher");
+        self.unpack_staged(|directory| {
+            for (asset, bytes) in downloaded {
+                let destination = directory.join(&asset.extract_to);
+                fs::create_dir_all(&destination)?;
+                if asset.is_archive() {
+                    info!("Unpacking {} into {destination:?}", asset.filename);
+                    unpack_archive(bytes.reader(), &destination, self.log_extracted_files)?;
+                } else {
+                    info!("Writing {} into {destination:?}", asset.filename);
+                    fs::write(destination.join(&asset.filename), &bytes)?;
+                }
+            }
+            Ok(())
+        })?;
+        // The assets aren't described by a release manifest, so don't verify them against a previous one.
+        let _ = fs::remove_file(sidecar_path(
+            &self.versiondata_path(),
+            XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX,
+        ));
+        let _ = fs::remove_file(sidecar_path(
+            &self.versiondata_path(),
+            XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX,
+        ));
+        info!("Wrote XIVLauncher files");
+        Ok(())
+    }
+
+    /// Creates a new XLCore installation or overwrites an existing XLCore installion with a new one.
+    pub async fn install(&self) -> Result<()> {
+        let install_location = &self.install_directory;
+
+        if let Some(assets) = &self.release.install_assets {
+            self.install_assets(assets).await?;
+        } else {
+            // Download/extract XLCore.
+            {
+                // Version data is only written once an install has finished, so it marks an existing install.
+                let action =
+                    install_action(&self.release.version, fs::exists(self.versiondata_path())?);
+                info!("{action}");
+                let progress_text = match self.changelog().await {
+                    Some(changelog) => {
+                        info!(
+                            "Changes in XIVLauncher {}:\n{}",
+                            self.release.version,
+                            changelog.trim()
+                        );
+                        match summarize_changelog(&changelog) {
+                            Some(summary) => format!("{action}: {summary}"),
+                            None => action,
+                        }
+                    }
+                    None => action,
+                };
+                info!("Downloading XIVLauncher release from {}", self.release.url);
+                self.set_progress(InstallPhase::Downloading, 0, &progress_text);
+                let cached_bytes = self.read_cached_release();
+                let from_cache = cached_bytes.is_some();
+                let (bytes, unpacked) = match cached_bytes {
+                    Some(bytes) => (bytes, false),
+                    None if self.can_stream_extract()? => {
+                        (self.download_and_unpack_streamed().await?, true)
+                    }
+                    None => (
+                        match self.download_backend {
+                            DownloadBackend::Aria2c => match http_client_required() {
+                                Some(reason) => {
+                                    warn!("Not downloading with aria2c as {reason}");
+                                    download_bytes(self.release.url.clone()).await?
+                                }
+                                None => self.download_with_aria2c().await?,
+                            },
+                            DownloadBackend::Reqwest => {
+                                download_bytes(self.release.url.clone()).await?
+                            }
+                        },
+                        false,
+                    ),
+                };
+                if !unpacked {
+                    if (bytes.len() as u64) < self.min_release_size {
+                        bail!(XlmError::ReleaseTooSmall {
+                            url: self.release.url.clone(),
+                            size: bytes.len() as u64,
+                            min_size: self.min_release_size,
+                        });
+                    }
+                    if let Some((signature_url, public_key)) = &self.signature {
+                        info!("Verifying XIVLauncher release signature from {signature_url}");
+                        self.set_progress(
+                            InstallPhase::Verifying,
+                            30,
+                            "Verifying XIVLauncher signature",
+                        );
+                        let signature = download_bytes(signature_url.clone()).await?;
+                        if let Err(err) = public_key.verify(&bytes, &signature) {
+                            bail!(XlmError::SignatureMismatch {
+                                reason: format!("{err:#}"),
+                            });
+                        }
+                        info!("XIVLauncher release signature is valid");
+                    }
+                    self.unpack_release(&bytes)?;
+                }
+                info!("Wrote XIVLauncher files");
+
+                let manifest = match &self.release.manifest_url {
+                    Some(manifest_url) => download_bytes_if_exists(manifest_url.clone()).await?,
+                    None => None,
+                };
+                let _ = fs::remove_file(sidecar_path(
+                    &self.versiondata_path(),
+                    XIVLAUNCHER_MANIFEST_LOCAL_SUFFIX,
+                ));
+                let _ = fs::remove_file(sidecar_path(
+                    &self.versiondata_path(),
+                    XIVLAUNCHER_VERIFIED_LOCAL_SUFFIX,
+                ));
+                match manifest {
+                    Some(manifest) => {
+                        let manifest = String::from_utf8_lossy(&manifest);
+                        info!("Verifying extracted XIVLauncher files against the release manifest");
+                        self.set_progress(
+                            InstallPhase::Verifying,
+                            60,
+                            "Verifying XIVLauncher files",
+                        );
+                        verify_sha256_manifest(install_location, &manifest)
+                            .context("XIVLauncher release failed manifest verification")?;
+                        info!("All XIVLauncher files match the release manifest");
+                        if let Err(err) = record_verified_install(
+                            install_location,
+                            &self.versiondata_path(),
+                            &self.release.version,
+                            &manifest,
+                        ) {
+                            warn!("Unable to record the XIVLauncher release manifest: {err:?}");
+                        }
+                    }
+                    None => debug!("No release manifest available, skipping file verification"),
+                }
+
+                if !from_cache {
+                    if let Err(err) = self.write_cached_release(&bytes) {
+                        warn!("Unable to add XIVLauncher release to the download cache: {err:?}");
+                    }
+                }
+            }
+
+            // Download/extract aria2c.
+            {
+                let aria_archive_bytes = match &self.aria_source {
+                    AriaSource::Embedded => {
+                        info!("Using embedded aria2c tarball");
+                        Bytes::from_static(ARIA2C_TARBALL_CONTENT)
+                    }
+                    AriaSource::Url(url) => {
+                        info!("Downloading remote aria2c tarball from {url}");
+                        self.set_progress(InstallPhase::Downloading, 70, "Downloading aria2c");
+                        download_bytes(url.clone()).await?
+                    }
+                    AriaSource::File(path) => {
+                        info!("Using local aria2c tarball at path: {path:?}");
+                        Bytes::from(fs::read(path)?)
+                    }
+                    AriaSource::GitHub { owner, repo, asset } => {
+                        info!("Resolving latest aria2c tarball from {owner}/{repo}");
+                        self.set_progress(InstallPhase::Downloading, 70, "Downloading aria2c");
+                        let release = ReleaseAssetInfo::from_github(owner, repo, asset).await?;
+                        info!(
+                            "Downloading aria2c tarball {} from {}",
+                            release.version, release.url
+                        );
+                        download_bytes(release.url).await?
+                    }
+                };
+
+                let mut archive = Archive::new(GzDecoder::new(aria_archive_bytes.reader()));
+
+                info!("Unpacking aria2c tarball");
+                self.set_progress(InstallPhase::Extracting, 80, "Unpacking aria2c");
+                archive.unpack(install_location)?;
+                info!("Wrote aria2c binary");
+            }
+        }
+
+        // Some tarballs are repacked on filesystems that don't preserve modes, so ensure the binaries can be run.
+        for binary in [XIVLAUNCHER_BIN_FILENAME, self.aria_binary_name.as_str()] {
+            let path = install_location.join(binary);
+            if fs::exists(&path)? {
+                ensure_executable(&path)?;
+            }
+        }
+
+        info!("Ensuring the install contains the correct binaries");
+        self.set_progress(InstallPhase::Verifying, 90, "Checking installed binaries");
+        if self.skip_aria_validation {
+            validate_install_layout(install_location, XIVLAUNCHER_BIN_FILENAME, None)?;
+            if !fs::exists(install_location.join(&self.aria_binary_name))? {
+                warn!(
+                    "aria2c tarball does not contain a binary named '{}', continuing as aria2c validation is skipped",
+                    self.aria_binary_name
+                );
+            }
+        } else {
+            validate_install_layout(
+                install_location,
+                XIVLAUNCHER_BIN_FILENAME,
+                Some(self.aria_binary_name.as_str()),
+            )?;
+            self.set_progress(InstallPhase::Verifying, 92, "Testing aria2c");
+            check_aria2c(&install_location.join(&self.aria_binary_name)).await?;
+        }
+
+        // Write local version info for release.
+        {
+            self.set_progress(
+                InstallPhase::Finishing,
+                95,
+                "Writing XIVLauncher version data",
+            );
+            let versiondata_path = self.versiondata_path();
+            if let Some(parent) = versiondata_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut file = File::options()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .append(false)
+                .open(&versiondata_path)?;
+            file.write_all(self.release.version.as_bytes())?;
+            info!(
+                "Wrote versiondata to {versiondata_path:?} with version {}",
+                self.release.version
+            );
+        }
+        self.set_progress(InstallPhase::Finishing, 100, "Finishing up");
+
+        Ok(())
+    }
+}
+
+/// Describe installing the given version of XIVLauncher, either as an update of an existing install or a fresh one.
+fn install_action(version: &str, updating: bool) -> String {
+    match updating {
+        true => format!("Updating XIVLauncher to {version}"),
+        false => format!("Installing XIVLauncher {version}"),
+    }
+}
+
+/// Ensure an install directory contains the XIVLauncher and aria2c binaries with the given names, and that both
+/// are executable ELF files. The aria2c binary isn't checked if no name is given.
+pub fn validate_install_layout(
+    directory: &Path,
+    binary_name: &str,
+    aria_name: Option<&str>,
+) -> Result<()> {
+    let binaries = iter::once(("XIVLauncher", binary_name))
+        .chain(aria_name.map(|aria_name| ("aria2c", aria_name)));
+    for (archive, name) in binaries {
+        let path = directory.join(name);
+        let reason = match fs::metadata(&path) {
+            Err(_) => Some(format!("it does not contain a binary named '{name}'")),
+            Ok(metadata) if !metadata.is_file() => Some(format!("'{name}' is not a file")),
+            Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 => {
+                Some(format!("'{name}' is not executable"))
+            }
+            Ok(_) => {
+                let mut magic = [0; ELF_MAGIC.len()];
+                match File::open(&path).and_then(|mut file| file.read_exact(&mut magic)) {
+                    Ok(()) if magic == ELF_MAGIC => None,
+                    Ok(()) => Some(format!("'{name}' is not an ELF binary")),
+                    Err(err) => Some(format!("unable to read '{name}': {err}")),
+                }
+            }
+        };
+        if let Some(reason) = reason {
+            let err = XlmError::IncompatibleArchive {
+                archive: String::from(archive),
+                reason,
+            };
+            error!("{err}");
+            bail!(err)
+        }
+    }
+    Ok(())
+}
+
+/// Detect whether XLM is running under an x86_64 emulator on another architecture, returning the emulator's name.
+///
+/// Emulators such as FEX and Box64 report the host CPU in `/proc/cpuinfo` or set their own environment variables.
+pub fn detect_emulator() -> Option<&'static str> {
+    if env::var_os("FEX_ROOTFS").is_some() {
+        return Some("FEX");
+    }
+    if env::vars_os().any(|(key, _)| key.to_string_lossy().starts_with("BOX64_")) {
+        return Some("Box64");
+    }
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    if cpuinfo.lines().any(|line| {
+        line.split_once(':').is_some_and(|(key, value)| {
+            key.trim() == "model name" && value.trim().starts_with("FEX")
+        })
+    }) {
+        return Some("FEX");
+    }
+    // ARM CPUs list an implementer instead of a vendor, which x86_64 CPUs never do.
+    if cpuinfo
+        .lines()
+        .any(|line| line.starts_with("CPU implementer"))
+    {
+        return Some("an unknown emulator");
+    }
+    None
+}
+
+/// Run `aria2c --version` to make sure the extracted aria2c binary can run on this system, as an incompatible
+/// binary otherwise won't be noticed until XIVLauncher tries to download the game.
+async fn check_aria2c(path: &Path) -> Result<()> {
+    info!("Checking aria2c can be run");
+    let output = tokio::time::timeout(
+        ARIA2C_CHECK_TIMEOUT,
+        tokio::process::Command::new(path)
+            .arg("--version")
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await;
+    let reason = match output {
+        Ok(Ok(output)) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            match stdout.lines().next() {
+                Some(version) if version.starts_with("aria2 version") => {
+                    info!("aria2c is working ({version})");
+                    return Ok(());
+                }
+                _ => String::from("`aria2c --version` did not print a version"),
+            }
+        }
+        Ok(Ok(output)) => format!("`aria2c --version` exited with {}", output.status),
+        Ok(Err(err)) => format!("unable to run aria2c: {err}"),
+        Err(_) => format!(
+            "`aria2c --version` did not finish within {}s",
+            ARIA2C_CHECK_TIMEOUT.as_secs()
+        ),
+    };
+    error!("The aria2c binary is not compatible with this system. Try a different binary with `--aria-source url:<url>` or `--aria-source file:<path>`.");
+    bail!(XlmError::IncompatibleArchive {
+        archive: String::from("aria2c"),
+        reason,
+    })
+}
+
+/// The path of the checksum stored alongside a cached release archive.
+fn cached_checksum_path(path: &Path) -> PathBuf {
+    let mut checksum_path = path.as_os_str().to_owned();
+    checksum_path.push(".sha256");
+    PathBuf::from(checksum_path)
+}
+
+/// Set the permissions of the file at the given path to `0o755` if it isn't executable by everyone.
+fn ensure_executable(path: &Path) -> Result<()> {
+    let mode = fs::metadata(path)
+        .with_context(|| format!("unable to read permissions of {path:?}"))?
+        .permissions()
+        .mode();
+    if mode & 0o111 != 0o111 {
+        info!(
+            "{path:?} is not executable (mode {:o}), setting its mode to 755",
+            mode & 0o777
+        );
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
+
+/// Extract a release tarball into the given directory, logging the path and size of each entry when enabled.
+fn unpack_archive(reader: impl Read, destination: &Path, log_extracted_files: bool) -> Result<()> {
+    let mut archive = Archive::new(GzDecoder::new(reader));
+    if !log_extracted_files {
+        archive.unpack(destination)?;
+        return Ok(());
+    }
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        debug!("Extracting {path:?} ({} bytes)", entry.size());
+        if !entry.unpack_in(destination)? {
+            bail!("Release tarball entry {path:?} points outside of {destination:?}");
+        }
+    }
+    Ok(())
+}
+
+/// Move the files of a release tarball that wraps everything inside of a single top-level directory up into the
+/// given directory, then ensure it contains the XIVLauncher binary.
+fn unwrap_release_directory(directory: &Path) -> Result<()> {
+    if !fs::exists(directory.join(XIVLAUNCHER_BIN_FILENAME))? {
+        let entries = fs::read_dir(directory)?.collect::<Result<Vec<_>, _>>()?;
+        if let [entry] = entries.as_slice() {
+            if entry.file_type()?.is_dir()
+                && fs::exists(entry.path().join(XIVLAUNCHER_BIN_FILENAME))?
+            {
+                info!(
+                    "Release tarball wraps its files inside of {:?} - moving them up a directory",
+                    entry.file_name()
+                );
+                // Move the wrapper aside first in case it contains an entry with the same name as itself.
+                let wrapper = directory.join(format!(".xlm-unwrap{XLM_STAGING_SUFFIX}"));
+                fs::rename(entry.path(), &wrapper)?;
+                for child in fs::read_dir(&wrapper)? {
+                    let child = child?;
+                    fs::rename(child.path(), directory.join(child.file_name()))?;
+                }
+                fs::remove_dir(&wrapper)?;
+            }
+        }
+    }
+
+    if !fs::exists(directory.join(XIVLAUNCHER_BIN_FILENAME))? {
+        let err = XlmError::IncompatibleArchive {
+            archive: String::from("XIVLauncher"),
+            reason: format!("it does not contain a binary named '{XIVLAUNCHER_BIN_FILENAME}'"),
+        };
+        error!("{err}");
+        bail!(err)
+    }
+    Ok(())
+}
+
+/// Extract only the entries of a release tarball whose size or modification time differ from the files already
+/// inside of the install directory, then remove any installed files that are no longer part of the release.
+///
+/// Returns `Ok(false)` without touching the install directory if any file in the tarball has no modification time,
+/// or if the XIVLauncher binary isn't at the root of the tarball.
+fn unpack_incremental(
+    bytes: &Bytes,
+    install_location: &Path,
+    log_extracted_files: bool,
+) -> Result<bool> {
+    // Check every file can be compared before modifying anything, and that files aren't wrapped in a directory.
+    let mut archive = Archive::new(GzDecoder::new(bytes.clone().reader()));
+    let mut contains_binary = false;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_file() && entry.header().mtime()? == 0 {
+            return Ok(false);
+        }
+        contains_binary |= entry
+            .path()?
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .eq([Component::Normal(XIVLAUNCHER_BIN_FILENAME.as_ref())]);
+    }
+    if !contains_binary {
+        return Ok(false);
+    }
+
+    info!("Incrementally unpacking XIVLauncher release tarball");
+    let mut archive = Archive::new(GzDecoder::new(bytes.clone().reader()));
+    let mut archive_paths = HashSet::new();
+    let (mut extracted, mut unchanged) = (0, 0);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry
+            .path()?
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect::<PathBuf>();
+        archive_paths.insert(path.clone());
+
+        if entry.header().entry_type().is_file()
+            && is_unchanged(&install_location.join(&path), entry.header())
+        {
+            unchanged += 1;
+            continue;
+        }
+        if log_extracted_files {
+            debug!("Extracting {path:?} ({} bytes)", entry.size());
+        }
+        if !entry.unpack_in(install_location)? {
+            bail!("Release tarball entry {path:?} points outside of the install directory");
+        }
+        extracted += 1;
+    }
+
+    let removed = remove_stale_files(install_location, Path::new(""), &archive_paths)?;
+    info!("Extracted {extracted} changed files, kept {unchanged} unchanged files and removed {removed} stale files");
+    Ok(true)
+}
+
+/// Condense a changelog into its first line of content, without markdown headings or list markers, so it can be
+/// shown alongside install progress.
+fn summarize_changelog(changelog: &str) -> Option<String> {
+    let line = changelog
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.trim_start_matches(['-', '*', '+', ' ']))
+        .find(|line| !line.is_empty())?;
+    if line.chars().count() <= CHANGELOG_SUMMARY_MAX_CHARS {
+        return Some(line.to_string());
+    }
+    let truncated = line
+        .chars()
+        .take(CHANGELOG_SUMMARY_MAX_CHARS - 3)
+        .collect::<String>();
+    Some(format!("{}...", truncated.trim_end()))
+}
+
+/// Extract only the entries of a release tarball whose path matches one of the given patterns into an existing
+/// install directory, leaving every other file untouched.
+fn unpack_matching(
+    bytes: &Bytes,
+    install_location: &Path,
+    patterns: &[String],
+    log_extracted_files: bool,
+) -> Result<()> {
+    info!("Unpacking XIVLauncher release tarball entries matching {patterns:?}");
+    let mut archive = Archive::new(GzDecoder::new(bytes.clone().reader()));
+    let (mut extracted, mut skipped) = (0, 0);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry
+            .path()?
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect::<PathBuf>();
+        let path_str = path.to_string_lossy();
+        if !patterns
+            .iter()
+            .any(|pattern| wildcard_matches(pattern, &path_str))
+        {
+            skipped += 1;
+            continue;
+        }
+        if log_extracted_files {
+            debug!("Extracting {path:?} ({} bytes)", entry.size());
+        }
+        if !entry.unpack_in(install_location)? {
+            bail!("Release tarball entry {path:?} points outside of the install directory");
+        }
+        extracted += 1;
+    }
+    if extracted == 0 {
+        warn!("No XIVLauncher release tarball entries matched {patterns:?}, nothing was extracted");
+    }
+    info!("Extracted {extracted} matching files and skipped {skipped} other files");
+    Ok(())
+}
+
+/// Whether some text matches a pattern where `*` matches any run of characters and `?` matches any single character.
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` and the text position it is currently matched up to.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Whether the installed file at the given path has the same size and modification time as a tarball entry.
+fn is_unchanged(path: &Path, header: &Header) -> bool {
+    let (Ok(metadata), Ok(size), Ok(mtime)) =
+        (fs::symlink_metadata(path), header.size(), header.mtime())
+    else {
+        return false;
+    };
+    metadata.is_file()
+        && metadata.len() == size
+        && metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .is_some_and(|modified| modified.as_secs() == mtime)
+}
+
+/// Recursively remove everything inside of `root.join(relative)` that isn't part of the given archive paths,
+/// returning the number of files removed.
+fn remove_stale_files(
+    root: &Path,
+    relative: &Path,
+    archive_paths: &HashSet<PathBuf>,
+) -> Result<usize> {
+    let mut removed = 0;
+    for entry in fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        let is_dir = entry.file_type()?.is_dir();
+        if is_dir
+            && archive_paths
+                .iter()
+                .any(|archive_path| archive_path.starts_with(&path))
+        {
+            removed += remove_stale_files(root, &path, archive_paths)?;
+        } else if !archive_paths.contains(&path) {
+            debug!("Removing stale file {path:?}");
+            if is_dir {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty fixture directory for a test, removed when dropped so it is cleaned up even if the test fails.
+    struct FixtureDirectory(PathBuf);
+
+    impl FixtureDirectory {
+        /// Create an empty fixture directory, replacing any left over from a previous run.
+        fn new(name: &str) -> Self {
+            let directory =
+                std::env::temp_dir().join(format!("xlm-test-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&directory);
+            fs::create_dir_all(&directory).unwrap();
+            Self(directory)
+        }
+    }
+
+    impl std::ops::Deref for FixtureDirectory {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for FixtureDirectory {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Write a fixture file with the given contents and permission mode.
+    fn write_fixture(directory: &Path, name: &str, contents: &[u8], mode: u32) {
+        let path = directory.join(name);
+        fs::write(&path, contents).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    /// An executable file that starts with the ELF magic number.
+    fn write_elf(directory: &Path, name: &str) {
+        write_fixture(directory, name, b"\x7fELF\x02\x01\x01", 0o755);
+    }
+
+    /// Get the reason an install layout was rejected.
+    fn layout_error(directory: &Path, aria_name: Option<&str>) -> String {
+        let err = validate_install_layout(directory, "XIVLauncher.Core", aria_name).unwrap_err();
+        match err.downcast_ref::<XlmError>() {
+            Some(XlmError::IncompatibleArchive { reason, .. }) => reason.clone(),
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    /// A gzipped release archive containing only an executable XIVLauncher binary.
+    fn release_archive() -> Vec<u8> {
+        let contents = b"\x7fELF\x02\x01\x01";
+        let mut header = Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        builder
+            .append_data(&mut header, XIVLAUNCHER_BIN_FILENAME, &contents[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// Install a fixture release from the download cache into `xlcore` inside of the given directory, returning the
+    /// text of the first progress update.
+    async fn first_install_progress(directory: &Path) -> String {
+        let release = ReleaseAssetInfo {
+            version: String::from("1.1.2"),
+            url: Url::parse("https://example.invalid/XIVLauncher.Core.tar.gz").unwrap(),
+            manifest_url: None,
+            changelog: None,
+            changelog_url: None,
+            install_assets: None,
+        };
+        let cache = directory.join("cache");
+        fs::create_dir_all(&cache).unwrap();
+        let archive = release_archive();
+        let cached_release = cache.join("1.1.2-XIVLauncher.Core.tar.gz");
+        fs::write(&cached_release, &archive).unwrap();
+        fs::write(
+            cached_checksum_path(&cached_release),
+            sha256_bytes(&archive),
+        )
+        .unwrap();
+
+        let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let installer = Installer::builder(release, directory.join("xlcore"))
+            .temp_directory(directory.join("tmp"))
+            .download_cache(&cache)
+            .min_release_size(0)
+            .keep_backups(0)
+            .skip_aria_validation(true)
+            .on_progress({
+                let updates = updates.clone();
+                move |progress| {
+                    updates.lock().unwrap().push((
+                        progress.phase,
+                        progress.percent,
+                        progress.text.to_string(),
+                    ))
+                }
+            })
+            .build();
+        installer.install().await.unwrap();
+
+        let updates = updates.lock().unwrap();
+        let (phase, percent, text) = updates.first().cloned().unwrap();
+        assert_eq!((phase, percent), (InstallPhase::Downloading, 0));
+        text
+    }
+
+    #[tokio::test]
+    async fn install_reports_fresh_install_progress() {
+        let directory = FixtureDirectory::new("install-fresh");
+        assert_eq!(
+            first_install_progress(&directory).await,
+            "Installing XIVLauncher 1.1.2"
+        );
+    }
+
+    #[tokio::test]
+    async fn install_reports_update_progress() {
+        let directory = FixtureDirectory::new("install-update");
+        let install_directory = directory.join("xlcore");
+        fs::create_dir_all(&install_directory).unwrap();
+        write_elf(&install_directory, XIVLAUNCHER_BIN_FILENAME);
+        fs::write(
+            install_directory.join(XIVLAUNCHER_VERSIONDATA_LOCAL_FILENAME),
+            "1.1.1",
+        )
+        .unwrap();
+        assert_eq!(
+            first_install_progress(&directory).await,
+            "Updating XIVLauncher to 1.1.2"
+        );
+    }
+
+    #[test]
+    fn install_action_describes_fresh_install() {
+        assert_eq!(
+            install_action("1.1.2", false),
+            "Installing XIVLauncher 1.1.2"
+        );
+    }
+
+    #[test]
+    fn install_action_describes_update() {
+        assert_eq!(
+            install_action("1.1.2", true),
+            "Updating XIVLauncher to 1.1.2"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_accepts_valid_layout() {
+        let directory = FixtureDirectory::new("layout-valid");
+        write_elf(&directory, "XIVLauncher.Core");
+        write_elf(&directory, "aria2c");
+        assert!(validate_install_layout(&directory, "XIVLauncher.Core", Some("aria2c")).is_ok());
+    }
+
+    #[test]
+    fn validate_install_layout_rejects_missing_binary() {
+        let directory = FixtureDirectory::new("layout-missing-binary");
+        write_elf(&directory, "aria2c");
+        assert_eq!(
+            layout_error(&directory, Some("aria2c")),
+            "it does not contain a binary named 'XIVLauncher.Core'"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_rejects_missing_aria2c() {
+        let directory = FixtureDirectory::new("layout-missing-aria2c");
+        write_elf(&directory, "XIVLauncher.Core");
+        assert_eq!(
+            layout_error(&directory, Some("aria2c")),
+            "it does not contain a binary named 'aria2c'"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_rejects_directory() {
+        let directory = FixtureDirectory::new("layout-directory");
+        fs::create_dir(directory.join("XIVLauncher.Core")).unwrap();
+        assert_eq!(
+            layout_error(&directory, None),
+            "'XIVLauncher.Core' is not a file"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_rejects_non_executable_file() {
+        let directory = FixtureDirectory::new("layout-not-executable");
+        write_fixture(
+            &directory,
+            "XIVLauncher.Core",
+            b"\x7fELF\x02\x01\x01",
+            0o644,
+        );
+        assert_eq!(
+            layout_error(&directory, None),
+            "'XIVLauncher.Core' is not executable"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_rejects_non_elf_file() {
+        let directory = FixtureDirectory::new("layout-not-elf");
+        write_fixture(&directory, "XIVLauncher.Core", b"#!/bin/sh\n", 0o755);
+        assert_eq!(
+            layout_error(&directory, None),
+            "'XIVLauncher.Core' is not an ELF binary"
+        );
+    }
+
+    #[test]
+    fn validate_install_layout_skips_aria2c_without_name() {
+        let directory = FixtureDirectory::new("layout-no-aria2c");
+        write_elf(&directory, "XIVLauncher.Core");
+        assert!(validate_install_layout(&directory, "XIVLauncher.Core", None).is_ok());
+    }
+}
@@ -0,0 +1,668 @@
+use crate::{core::GITHUB_API_URL, error::XlmError, integrity::sha256_bytes};
+use anyhow::{bail, Context, Result};
+use bytes::{Bytes, BytesMut};
+use log::{debug, info};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::USER_AGENT,
+    redirect, Client, StatusCode, Url,
+};
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        WebPkiServerVerifier,
+    },
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use std::{
+    fmt::Display,
+    io::{self, Read},
+    iter,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// The User-Agent sent with outbound requests unless another is set with [`configure_http_client`].
+pub const DEFAULT_USER_AGENT: &str =
+    concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// How long to wait for a response to each reachability check.
+const REACHABILITY_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long to wait between reachability checks.
+const REACHABILITY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many downloaded chunks can be waiting to be read from a [`DownloadReader`] before the download pauses.
+const STREAM_CHANNEL_CAPACITY: usize = 64;
+
+/// The most redirects to follow for a single request.
+const MAX_REDIRECTS: usize = 10;
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+static CURRENT_USER_AGENT: OnceLock<String> = OnceLock::new();
+/// The base url of the GitHub API release information is requested from, set with [`configure_http_client`].
+static CURRENT_GITHUB_API_URL: OnceLock<Url> = OnceLock::new();
+/// The IP version outbound requests connect over, set with [`configure_http_client`].
+static CURRENT_IP_VERSION: OnceLock<IpVersion> = OnceLock::new();
+/// The only hosts outbound requests may be sent to, if requests have been restricted with [`restrict_hosts`].
+static ALLOWED_HOSTS: RwLock<Option<Vec<String>>> = RwLock::new(None);
+
+/// The certificate fingerprints TLS connections to each host must match, added with [`pin_certificate`].
+static CERTIFICATE_PINS: RwLock<Vec<(String, CertificatePin)>> = RwLock::new(Vec::new());
+
+/// Whether optional outbound requests should be skipped, set with [`set_offline`].
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// The SHA-256 fingerprint of a TLS certificate, as hex optionally separated by colons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificatePin(String);
+
+impl FromStr for CertificatePin {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fingerprint = s.trim().replace(':', "").to_ascii_lowercase();
+        if fingerprint.len() != 64 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("certificate pins must be a SHA-256 fingerprint of 64 hex characters");
+        }
+        Ok(Self(fingerprint))
+    }
+}
+
+impl Display for CertificatePin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Only allow TLS connections to the given host when its certificate, or a certificate in its chain, matches
+/// one of the fingerprints pinned for it. Normal certificate verification still applies on top of this.
+pub fn pin_certificate(host: &str, pin: CertificatePin) {
+    info!("Pinning the TLS certificate of {host} to {pin}");
+    CERTIFICATE_PINS
+        .write()
+        .unwrap()
+        .push((host.to_string(), pin));
+}
+
+/// Verifies certificates as normal, additionally rejecting certificates for hosts with pinned fingerprints that
+/// don't match any of them.
+#[derive(Debug)]
+struct PinnedCertificateVerifier(Arc<WebPkiServerVerifier>);
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str();
+        let pins = CERTIFICATE_PINS
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(pinned_host, _)| *pinned_host == host)
+            .map(|(_, pin)| pin.0.clone())
+            .collect::<Vec<_>>();
+        if !pins.is_empty()
+            && !iter::once(end_entity)
+                .chain(intermediates)
+                .any(|cert| pins.contains(&sha256_bytes(cert)))
+        {
+            return Err(rustls::Error::General(format!(
+                "the certificate of {host} does not match any of its pinned fingerprints"
+            )));
+        }
+        self.0
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.0.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.supported_verify_schemes()
+    }
+}
+
+/// Which IP version outbound requests connect over.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IpVersion {
+    /// Connect over whichever IP version is available.
+    #[default]
+    Auto,
+    V4,
+    V6,
+}
+
+impl FromStr for IpVersion {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "v4" => Ok(Self::V4),
+            "v6" => Ok(Self::V6),
+            _ => Err("valid ip versions are 'auto', 'v4' or 'v6'"),
+        }
+    }
+}
+
+impl Display for IpVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            IpVersion::Auto => write!(f, "auto"),
+            IpVersion::V4 => write!(f, "v4"),
+            IpVersion::V6 => write!(f, "v6"),
+        }
+    }
+}
+
+/// Resolves hostnames to only the addresses of a single IP version.
+struct IpVersionResolver(IpVersion);
+
+impl Resolve for IpVersionResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let ip_version = self.0;
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| match ip_version {
+                    IpVersion::Auto => true,
+                    IpVersion::V4 => addr.is_ipv4(),
+                    IpVersion::V6 => addr.is_ipv6(),
+                })
+                .collect::<Vec<_>>();
+            if addrs.is_empty() {
+                return Err(format!("{} has no IP{ip_version} addresses", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Skip optional outbound requests such as update checks, only sending requests that are required to continue.
+pub fn set_offline() {
+    info!("Offline mode enabled, optional outbound requests will be skipped");
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+/// Whether optional outbound requests should be skipped as set with [`set_offline`].
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Restrict outbound requests to hosts allowed with [`allow_host`], failing requests to any other host.
+pub fn restrict_hosts() {
+    info!("Outbound requests are restricted to configured hosts");
+    *ALLOWED_HOSTS.write().unwrap() = Some(Vec::new());
+}
+
+/// Whether outbound requests have been restricted with [`restrict_hosts`].
+pub fn hosts_restricted() -> bool {
+    ALLOWED_HOSTS.read().unwrap().is_some()
+}
+
+/// Allow outbound requests to the given host when requests have been restricted.
+pub fn allow_host(host: &str) {
+    if let Some(hosts) = ALLOWED_HOSTS.write().unwrap().as_mut() {
+        if !hosts.iter().any(|allowed| allowed == host) {
+            info!("Allowing outbound requests to {host}");
+            hosts.push(host.to_string());
+        }
+    }
+}
+
+/// Fail if outbound requests have been restricted and the given url's host hasn't been allowed.
+pub fn check_host_allowed(url: &Url) -> Result<()> {
+    if is_host_allowed(url) {
+        Ok(())
+    } else {
+        bail!(XlmError::HostNotAllowed {
+            host: url.host_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+fn is_host_allowed(url: &Url) -> bool {
+    match &*ALLOWED_HOSTS.read().unwrap() {
+        Some(hosts) => url
+            .host_str()
+            .is_some_and(|host| hosts.iter().any(|allowed| allowed == host)),
+        None => true,
+    }
+}
+
+/// Create the TLS configuration used by the shared client, which verifies certificates against the web PKI roots
+/// and any pinned fingerprints.
+fn tls_config() -> Result<ClientConfig> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let verifier =
+        WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone()).build()?;
+    let mut config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier(verifier)))
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+fn build_client(user_agent: &str, ip_version: IpVersion) -> Result<Client> {
+    let mut builder = Client::builder().use_preconfigured_tls(tls_config()?);
+    if ip_version != IpVersion::Auto {
+        builder = builder.dns_resolver(Arc::new(IpVersionResolver(ip_version)));
+    }
+    builder
+        .user_agent(user_agent)
+        // Redirects must also stay within the allowed hosts.
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if !is_host_allowed(attempt.url()) {
+                let host = attempt.url().host_str().unwrap_or_default().to_string();
+                attempt.error(XlmError::HostNotAllowed { host }.to_string())
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(Into::into)
+}
+
+/// Set the User-Agent sent with all outbound requests, including those made with the global octocrab instance,
+/// the IP version downloads connect over and the base url of the GitHub API octocrab requests are sent to.
+///
+/// This must be called before any requests are made.
+pub fn configure_http_client(
+    user_agent: &str,
+    ip_version: IpVersion,
+    github_api_url: &Url,
+) -> Result<()> {
+    let client = build_client(user_agent, ip_version)?;
+    if HTTP_CLIENT.set(client).is_err() {
+        bail!("Unable to set the User-Agent as the HTTP client has already been used");
+    }
+    let _ = CURRENT_USER_AGENT.set(user_agent.to_string());
+    let _ = CURRENT_IP_VERSION.set(ip_version);
+
+    // Relative API routes are joined onto the base url, so it must end with a slash to keep any path it has.
+    let mut github_api_url = github_api_url.clone();
+    if !github_api_url.path().ends_with('/') {
+        github_api_url.set_path(&format!("{}/", github_api_url.path()));
+    }
+    if CURRENT_GITHUB_API_URL.set(github_api_url.clone()).is_err() {
+        bail!("Unable to set the GitHub API url as it has already been used");
+    }
+    octocrab::initialise(
+        octocrab::Octocrab::builder()
+            .base_uri(github_api_url.as_str())?
+            .add_header(USER_AGENT, user_agent.to_string())
+            .build()?,
+    );
+    debug!("Using User-Agent {user_agent} over IP version {ip_version} with GitHub API {github_api_url}");
+    Ok(())
+}
+
+/// Get the base url of the GitHub API release information is requested from.
+pub fn github_api_url() -> &'static Url {
+    CURRENT_GITHUB_API_URL
+        .get_or_init(|| Url::parse(GITHUB_API_URL).expect("GitHub API url should be valid"))
+}
+
+/// Get the User-Agent sent with outbound requests, for tools that make requests outside of the shared client.
+pub fn user_agent() -> &'static str {
+    CURRENT_USER_AGENT
+        .get()
+        .map_or(DEFAULT_USER_AGENT, String::as_str)
+}
+
+/// Get the IP version outbound requests connect over.
+pub fn ip_version() -> IpVersion {
+    CURRENT_IP_VERSION.get().copied().unwrap_or_default()
+}
+
+/// Whether any certificate pins have been added with [`pin_certificate`].
+pub fn certificates_pinned() -> bool {
+    !CERTIFICATE_PINS.read().unwrap().is_empty()
+}
+
+/// Why downloads must be sent through the HTTP client rather than an external downloader, if they must.
+///
+/// External downloaders such as aria2c make their own connections, so they can't enforce host restrictions,
+/// certificate pins or injected faults, and can only be limited to IPv4.
+pub fn http_client_required() -> Option<&'static str> {
+    if hosts_restricted() {
+        return Some("outbound requests are restricted to configured hosts");
+    }
+    if certificates_pinned() {
+        return Some("certificate pins are configured");
+    }
+    if ip_version() == IpVersion::V6 {
+        return Some("outbound requests are restricted to IPv6");
+    }
+    #[cfg(feature = "test-hooks")]
+    if fault_injection::active() {
+        return Some("faults are being injected into downloads");
+    }
+    None
+}
+
+/// Get the HTTP client shared by all outbound requests.
+pub fn http_client() -> &'static Client {
+    HTTP_CLIENT.get_or_init(|| {
+        build_client(DEFAULT_USER_AGENT, IpVersion::Auto).expect("failed to build HTTP client")
+    })
+}
+
+/// Download the full contents of the given url, logging how long the request took and its outcome.
+pub async fn download_bytes(url: Url) -> Result<Bytes> {
+    check_host_allowed(&url)?;
+    debug!("Starting download of {url}");
+    let start = Instant::now();
+    let result = async {
+        let request = http_client().get(url.clone());
+        #[cfg(feature = "test-hooks")]
+        let request = fault_injection::apply(request, &url);
+        let response = request.send().await?.error_for_status()?;
+        response.bytes().await
+    }
+    .await;
+    let elapsed = start.elapsed();
+
+    match result {
+        Ok(bytes) => {
+            debug!(
+                "Download of {url} succeeded in {}ms ({} bytes)",
+                elapsed.as_millis(),
+                bytes.len()
+            );
+            info!("Downloaded {url} in {:.2}s", elapsed.as_secs_f32());
+            Ok(bytes)
+        }
+        Err(err) => {
+            let err = XlmError::network(url.clone(), err);
+            debug!(
+                "Download of {url} failed after {}ms: {err:?}",
+                elapsed.as_millis()
+            );
+            Err(err.into())
+        }
+    }
+}
+
+/// Start downloading the given url, returning a reader that yields the body as it arrives and a handle that
+/// resolves to the full body once the download has finished.
+///
+/// The reader blocks while waiting for data, so it must be read outside of async code, such as with
+/// [`tokio::task::block_in_place`]. If the download fails the reader returns an error instead of ending early, and
+/// dropping the reader stops the download.
+pub async fn download_streamed(url: Url) -> Result<(DownloadReader, JoinHandle<Result<Bytes>>)> {
+    check_host_allowed(&url)?;
+    debug!("Starting streamed download of {url}");
+    let start = Instant::now();
+    let request = http_client().get(url.clone());
+    #[cfg(feature = "test-hooks")]
+    let request = fault_injection::apply(request, &url);
+    let mut response = async { request.send().await?.error_for_status() }
+        .await
+        .map_err(|err| XlmError::network(url.clone(), err))?;
+
+    let (tx, rx) = mpsc::channel(STREAM_CHANNEL_CAPACITY);
+    let download = tokio::spawn(async move {
+        let mut body = BytesMut::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    body.extend_from_slice(&chunk);
+                    tx.send(Ok(chunk))
+                        .await
+                        .ok()
+                        .with_context(|| format!("download of {url} stopped being read"))?;
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    let _ = tx.send(Err(io::Error::other(err.to_string()))).await;
+                    let err = XlmError::network(url.clone(), err);
+                    debug!(
+                        "Streamed download of {url} failed after {}ms: {err:?}",
+                        start.elapsed().as_millis()
+                    );
+                    return Err(err.into());
+                }
+            }
+        }
+        debug!(
+            "Streamed download of {url} succeeded in {}ms ({} bytes)",
+            start.elapsed().as_millis(),
+            body.len()
+        );
+        info!("Downloaded {url} in {:.2}s", start.elapsed().as_secs_f32());
+        Ok(body.freeze())
+    });
+    Ok((
+        DownloadReader {
+            rx,
+            chunk: Bytes::new(),
+        },
+        download,
+    ))
+}
+
+/// Reads the body of a download started with [`download_streamed`] as it arrives.
+pub struct DownloadReader {
+    rx: mpsc::Receiver<io::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl Read for DownloadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.chunk = chunk?,
+                // The download has finished.
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+/// Download the full contents of the given url, returning `None` if the server reports that it does not exist.
+pub async fn download_bytes_if_exists(url: Url) -> Result<Option<Bytes>> {
+    match download_bytes(url).await {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err)
+            if matches!(
+                err.downcast_ref::<XlmError>(),
+                Some(XlmError::Network { source, .. }) if source.status() == Some(StatusCode::NOT_FOUND)
+            ) =>
+        {
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Send a single HEAD request to the given url, returning the status it responded with.
+///
+/// Any response counts as reachable, even one with an unsuccessful status.
+pub async fn check_reachable(url: &Url) -> reqwest::Result<StatusCode> {
+    http_client()
+        .head(url.clone())
+        .timeout(REACHABILITY_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map(|response| response.status())
+}
+
+/// Repeatedly send HEAD requests to the given url until any response is received or the timeout elapses.
+///
+/// Returns whether the url became reachable.
+pub async fn wait_for_reachable(url: &Url, timeout: Duration) -> bool {
+    let start = Instant::now();
+    loop {
+        match http_client()
+            .head(url.clone())
+            .timeout(REACHABILITY_REQUEST_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(_) => {
+                debug!(
+                    "{url} became reachable after {}ms",
+                    start.elapsed().as_millis()
+                );
+                return true;
+            }
+            Err(err) => debug!("{url} is not reachable yet: {err}"),
+        }
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        tokio::time::sleep(REACHABILITY_POLL_INTERVAL).await;
+    }
+}
+
+/// Deterministic failures for testing how XLM handles failed requests, configured with the `XLM_FAULT_HTTP`
+/// environment variable as a comma-separated list of faults. Only compiled in with the `test-hooks` feature.
+///
+/// - `timeout:<count>` makes the first `count` requests sent with the HTTP client time out, such as downloads and
+///   web release version requests.
+/// - `ratelimit:<count>` makes the first `count` GitHub release requests respond as if GitHub is rate limiting them
+///   and asked to be retried after a second.
+/// - `empty-release:<count>` makes the first `count` GitHub releases look like they have no assets yet.
+#[cfg(feature = "test-hooks")]
+pub(crate) mod fault_injection {
+    use log::warn;
+    use reqwest::{RequestBuilder, Url};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            OnceLock,
+        },
+        time::Duration,
+    };
+
+    pub const TIMEOUT: &str = "timeout";
+    pub const RATE_LIMIT: &str = "ratelimit";
+    pub const EMPTY_RELEASE: &str = "empty-release";
+
+    static REMAINING_FAULTS: OnceLock<HashMap<String, AtomicU32>> = OnceLock::new();
+
+    /// Parse a list of faults into how many of each kind remain to be injected.
+    pub(super) fn parse(faults: &str) -> HashMap<String, AtomicU32> {
+        faults
+            .split(',')
+            .filter_map(|fault| {
+                let (kind, count) = fault.trim().split_once(':')?;
+                Some((kind.to_string(), AtomicU32::new(count.parse().ok()?)))
+            })
+            .collect()
+    }
+
+    fn remaining_faults() -> &'static HashMap<String, AtomicU32> {
+        REMAINING_FAULTS.get_or_init(|| parse(&std::env::var("XLM_FAULT_HTTP").unwrap_or_default()))
+    }
+
+    /// Use up one of the remaining injected faults of the given kind, returning whether there was one.
+    pub fn take(kind: &str) -> bool {
+        remaining_faults().get(kind).is_some_and(|remaining| {
+            remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                    count.checked_sub(1)
+                })
+                .is_ok()
+        })
+    }
+
+    /// Whether there are injected timeouts remaining.
+    pub fn active() -> bool {
+        remaining_faults()
+            .get(TIMEOUT)
+            .is_some_and(|remaining| remaining.load(Ordering::SeqCst) > 0)
+    }
+
+    /// Make the given request time out if there are injected timeouts remaining.
+    pub fn apply(request: RequestBuilder, url: &Url) -> RequestBuilder {
+        if take(TIMEOUT) {
+            warn!("Injecting a timeout into the request to {url}");
+            return request.timeout(Duration::from_nanos(1));
+        }
+        request
+    }
+}
+
+#[cfg(all(test, feature = "test-hooks"))]
+mod tests {
+    use super::*;
+    use crate::core::ReleaseAssetInfo;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn fault_injection_parses_fault_list() {
+        let faults = fault_injection::parse("timeout:2, ratelimit:1,invalid,empty-release:x");
+        let count = |kind: &str| faults.get(kind).map(|count| count.load(Ordering::SeqCst));
+        assert_eq!(count(fault_injection::TIMEOUT), Some(2));
+        assert_eq!(count(fault_injection::RATE_LIMIT), Some(1));
+        assert_eq!(count(fault_injection::EMPTY_RELEASE), None);
+    }
+
+    #[tokio::test]
+    async fn fault_injection_times_out_web_release_version_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut request = [0; 1024];
+                    let _ = stream.read(&mut request).await;
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\nConnection: close\r\n\r\n1.0.0\n")
+                        .await;
+                });
+            }
+        });
+
+        // Faults are read once, so this must be the only test that sends requests.
+        std::env::set_var("XLM_FAULT_HTTP", "timeout:1");
+        assert!(ReleaseAssetInfo::from_url(&base_url, "release.tar.gz")
+            .await
+            .is_err());
+        let release = ReleaseAssetInfo::from_url(&base_url, "release.tar.gz")
+            .await
+            .unwrap();
+        assert_eq!(release.version, "1.0.0");
+    }
+}
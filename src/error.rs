@@ -0,0 +1,138 @@
+use reqwest::Url;
+use std::{io, path::PathBuf};
+use thiserror::Error;
+
+/// Categories of failure that can occur while installing or launching XIVLauncher.
+///
+/// Fallible functions still return an [`anyhow::Result`], so these can be matched on with
+/// [`anyhow::Error::downcast_ref`].
+#[derive(Debug, Error)]
+pub enum XlmError {
+    /// A request to a remote server failed or returned an unsuccessful status.
+    #[error("failed to download {url}: {reason}")]
+    Network {
+        url: Url,
+        reason: String,
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// A request was made to a host that outbound requests aren't allowed to be sent to.
+    #[error("outbound requests to {host} are not allowed as it is not a configured host")]
+    HostNotAllowed { host: String },
+
+    /// Release information could not be obtained from GitHub.
+    #[error("failed to obtain release information for {owner}/{repo}")]
+    GitHubRelease {
+        owner: String,
+        repo: String,
+        #[source]
+        source: octocrab::Error,
+    },
+
+    /// A release does not contain the requested asset.
+    #[error("failed to find asset {asset} among the {} assets in release {release} (available: {})", available.len(), available.join(", "))]
+    ReleaseAssetNotFound {
+        asset: String,
+        release: String,
+        available: Vec<String>,
+    },
+
+    /// A release has no assets at all, which usually means it is still being published.
+    #[error("release {release} of {owner}/{repo} has no assets. It is likely still being published, please try again shortly")]
+    ReleaseHasNoAssets {
+        owner: String,
+        repo: String,
+        release: String,
+    },
+
+    /// A downloaded release archive is too small to be a real release.
+    #[error("release archive downloaded from {url} is only {size} bytes, which is less than the minimum of {min_size} bytes. The release URL or mirror is likely serving an error page instead of the release")]
+    ReleaseTooSmall { url: Url, size: u64, min_size: u64 },
+
+    /// A file did not match the hash it was expected to have.
+    #[error("file {path:?} does not match the manifest (expected {expected}, got {actual})")]
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+
+    /// A release archive did not match its detached signature.
+    #[error("release archive failed signature verification: {reason}")]
+    SignatureMismatch { reason: String },
+
+    /// An archive was extracted but does not contain what XLM needs from it.
+    #[error("{archive} tarball is unusable with XIVLauncher: {reason}")]
+    IncompatibleArchive { archive: String, reason: String },
+
+    /// The install directory could not be created or written to.
+    #[error("unable to write to the install directory {path:?}")]
+    InstallDirNotWritable {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// The temp directory could not be created or written to.
+    #[error("unable to write to the temp directory {path:?}")]
+    TempDirNotWritable {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Steam has not created the directory the compatibility tool is installed into.
+    #[error("unable to find the parent directory of the Steam compatibility tools path ({path:?}). This is likely because you have not ran Steam for the first time")]
+    SteamNotInitialised { path: PathBuf },
+
+    /// The Steam compatibility tools directory exists but can't be written to.
+    #[error("unable to write to the Steam compatibility tools path {path:?}. If Steam is installed as a Flatpak or Snap, its files may not be writable from outside of the sandbox - check the permissions of this path or run XLM from inside of the sandbox")]
+    CompatPathNotWritable {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// A compatibility tool file was not written as intended.
+    #[error("{path:?} was not written correctly: {reason}")]
+    InvalidCompatToolFile { path: PathBuf, reason: String },
+
+    /// An extra environment file contains a line that can't be used.
+    #[error("line {line} of extra env file {path:?} {reason}")]
+    InvalidEnvFile {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+
+    /// A path that is run as a program is missing or is not an executable file.
+    #[error("{path:?} is not an executable file")]
+    NotExecutable { path: PathBuf },
+
+    /// A required executable could not be found on `PATH`.
+    #[error("unable to find {0} on PATH")]
+    ExecutableNotFound(String),
+
+    /// XIVLauncher exited unsuccessfully shortly after being launched.
+    #[error("XIVLauncher exited with {status} within {timeout_secs}s of launching")]
+    LaunchUnhealthy { status: String, timeout_secs: u64 },
+}
+
+impl XlmError {
+    /// Create a [`XlmError::Network`] from a failed request, describing why it failed.
+    pub fn network(url: Url, source: reqwest::Error) -> Self {
+        let reason = if source.is_timeout() {
+            String::from("timed out")
+        } else if let Some(status) = source.status() {
+            format!("server responded with {status}")
+        } else {
+            String::from("request failed")
+        };
+        Self::Network {
+            url,
+            reason,
+            source,
+        }
+    }
+}
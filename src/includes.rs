@@ -1,29 +1,75 @@
+use std::{
+    env::temp_dir,
+    path::{Path, PathBuf},
+};
+
 pub const COMPATIBILITYTOOL_VDF_FILENAME: &str = "compatibilitytool.vdf";
 pub const TOOLMANIFEST_VDF_FILENAME: &str = "toolmanifest.vdf";
 pub const XLM_LAUNCHSCRIPT_FILENAME: &str = "xlm.sh";
 pub const XLM_BINARY_FILENAME: &str = "xlm";
 pub const XLM_COMPATDIR_DIRNAME: &str = "XLM";
+pub const XLM_README_FILENAME: &str = "XLM-README.txt";
+pub const XLM_COMPAT_XLCORE_DIRNAME: &str = "xlcore";
+pub const XLM_CACHE_DIRNAME: &str = "xlm";
+pub const XLM_CACHE_MARKER_FILENAME: &str = ".xlm-cache";
 
 /// toolmanifest.vdf content as a collection of bytes.
 pub const TOOLMANIFEST_VDF_CONTENT: &[u8] = include_bytes!("../static/toolmanifest.vdf");
 /// compatibilitytool.vdf content as a collection of bytes.
 pub const COMPATIBILITYTOOL_VDF_CONTENT: &[u8] = include_bytes!("../static/compatibilitytool.vdf");
+/// XLM-README.txt content as a collection of bytes.
+pub const XLM_README_CONTENT: &[u8] = include_bytes!("../static/XLM-README.txt");
 /// aria2c tarball content as a collection of bytes.
 pub const ARIA2C_TARBALL_CONTENT: &[u8] = include_bytes!("../static/aria2c-static.tar.gz");
 
+/// Get the path of the file XLM writes its logs to.
+pub fn get_logfile_path() -> PathBuf {
+    temp_dir().join(format!("{}.log", env!("CARGO_PKG_NAME")))
+}
+
+/// Get the path the log file of the previous run of XLM is moved to when it starts.
+pub fn get_previous_logfile_path() -> PathBuf {
+    temp_dir().join(format!("{}.old.log", env!("CARGO_PKG_NAME")))
+}
+
+/// Get the path of XLM's cache directory, if the system has a cache directory.
+pub fn get_cache_directory() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(XLM_CACHE_DIRNAME))
+}
+
+/// Quote a string so it is interpreted literally by a POSIX shell.
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
 /// Get the xlm.sh launch script as a pre-formatted string.
+///
+/// XIVLauncher is installed inside of the tool directory unless a separate data directory is given. When Steam runs
+/// the script a second time, that invocation waits `launch_guard_sleep` seconds and exits.
 pub fn get_launch_script(
     extra_env_vars: &Option<String>,
     extra_launch_args: &Option<String>,
+    data_directory: Option<&Path>,
+    launch_guard_sleep: u64,
 ) -> String {
+    let xlcore_dir = match data_directory {
+        Some(dir) => shell_quote(&dir.join(XLM_COMPAT_XLCORE_DIRNAME).display().to_string()),
+        None => format!("\"$tooldir/{XLM_COMPAT_XLCORE_DIRNAME}\""),
+    };
     format!(
         r#"#!/bin/env bash
 
 # Prevents launching twice.
-if [[ "$1" == "run" ]]; then sleep 1; exit; fi
+if [[ "$1" == "run" ]]; then sleep {launch_guard_sleep}; exit; fi
 
 tooldir="$(realpath "$(dirname "$0")")"
 
+# Variables pre + post launch scripts can rely on, kept stable across XLM versions.
+export XLM_TOOL_DIR="$tooldir"
+export XLM_INSTALL_DIR={}
+export XLM_COMPAT_MODE="$1"
+export XLM_ORIGINAL_LD_PRELOAD="$LD_PRELOAD"
+
 # XLM pre-launch scripts.
 if [ -d $tooldir/prelaunch.d ]; then
     for extension in $tooldir/prelaunch.d/*; do
@@ -35,7 +81,19 @@ if [ -d $tooldir/prelaunch.d ]; then
 fi
 unset extension
 
-PATH=$PATH:$tooldir/xlcore {} $tooldir/xlm launch {} --install-directory $tooldir/xlcore $4
+# Launch command arguments set when the compatibility tool was installed.
+xlm_launch_args=({})
+
+# Extra launch flags can be set through Steam's launch options with `XLM_LAUNCH_ARGS="..." %command%`.
+# Steam passes the verb and the game's executable before the game's arguments, which are forwarded to XIVLauncher.
+PATH=$PATH:$XLM_INSTALL_DIR {} $tooldir/xlm launch "${{xlm_launch_args[@]}}" --install-directory "$XLM_INSTALL_DIR" $XLM_LAUNCH_ARGS --steam-app-args "${{@:3}}"
+
+# The loader exits with 127 before XLM runs if it is missing shared libraries, so explain which.
+if [ $? -eq 127 ] && ldd "$tooldir/xlm" | grep -q "not found"; then
+    echo "XLM was unable to start as it is missing the following shared libraries:"
+    ldd "$tooldir/xlm" | grep "not found"
+    echo "Install them with your package manager or use a statically linked (musl) build of XLM."
+fi
 
 # XLM post-launch scripts.
 if [ -d $tooldir/postlaunch.d ]; then
@@ -48,7 +106,8 @@ if [ -d $tooldir/postlaunch.d ]; then
 fi
 unset extension
 "#,
-        extra_env_vars.as_deref().unwrap_or_default(),
-        extra_launch_args.as_deref().unwrap_or_default()
+        xlcore_dir,
+        extra_launch_args.as_deref().unwrap_or_default(),
+        extra_env_vars.as_deref().unwrap_or_default()
     )
 }
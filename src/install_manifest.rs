@@ -0,0 +1,143 @@
+use crate::{download::download_bytes, integrity::is_sha256_hex};
+use anyhow::{bail, Context, Result};
+use reqwest::Url;
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+/// Where an [`InstallManifest`] is read from.
+#[derive(Debug, Clone)]
+pub enum InstallManifestSource {
+    Url(Url),
+    File(PathBuf),
+}
+
+impl FromStr for InstallManifestSource {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("http://") || s.starts_with("https://") {
+            return Url::parse(s)
+                .map(Self::Url)
+                .map_err(|_| "install manifest url is not valid");
+        }
+        if s.is_empty() {
+            return Err("install manifest must be a url or a file path");
+        }
+        Ok(Self::File(PathBuf::from(s)))
+    }
+}
+
+impl Display for InstallManifestSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A list of assets that together make up an install of XIVLauncher, for distributions that don't ship it as a
+/// single release archive.
+///
+/// ```json
+/// {
+///   "version": "1.1.2",
+///   "assets": [
+///     { "url": "https://example.com/XIVLauncher.Core.tar.gz", "filename": "XIVLauncher.Core.tar.gz", "sha256": "..." },
+///     { "url": "https://example.com/aria2c", "filename": "aria2c", "extract-to": "." }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstallManifest {
+    /// The version of XIVLauncher the assets install.
+    pub version: String,
+    /// The assets to install, in order.
+    pub assets: Vec<InstallAsset>,
+}
+
+/// A single file of an [`InstallManifest`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct InstallAsset {
+    /// Where the asset is downloaded from.
+    pub url: Url,
+    /// The name of the asset. Assets ending with `.tar.gz` or `.tgz` are extracted, others are copied as this name.
+    pub filename: String,
+    /// The hex-encoded SHA-256 hash the downloaded asset must match.
+    pub sha256: Option<String>,
+    /// The directory inside of the install directory the asset is extracted or copied to.
+    #[serde(default)]
+    pub extract_to: PathBuf,
+}
+
+impl InstallManifest {
+    /// Read and validate an install manifest.
+    pub async fn load(source: &InstallManifestSource) -> Result<Self> {
+        let content = match source {
+            InstallManifestSource::Url(url) => download_bytes(url.clone()).await?.to_vec(),
+            InstallManifestSource::File(path) => fs::read(path)
+                .with_context(|| format!("unable to read install manifest {path:?}"))?,
+        };
+        let manifest: Self = serde_json::from_slice(&content)
+            .with_context(|| format!("install manifest {source} is not valid"))?;
+        manifest
+            .validate()
+            .with_context(|| format!("install manifest {source} is not valid"))?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.version.trim().is_empty() {
+            bail!("the version must not be empty");
+        }
+        if self.assets.is_empty() {
+            bail!("at least one asset must be listed");
+        }
+        for asset in &self.assets {
+            if !matches!(
+                Path::new(&asset.filename).components().collect::<Vec<_>>()[..],
+                [Component::Normal(_)]
+            ) {
+                bail!(
+                    "asset filename {:?} must be a plain file name",
+                    asset.filename
+                );
+            }
+            if !asset
+                .extract_to
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                bail!(
+                    "asset {} extracts to {:?}, which is outside of the install directory",
+                    asset.filename,
+                    asset.extract_to
+                );
+            }
+            if asset
+                .sha256
+                .as_deref()
+                .is_some_and(|hash| !is_sha256_hex(hash))
+            {
+                bail!(
+                    "asset {} does not have a valid SHA-256 hash",
+                    asset.filename
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InstallAsset {
+    /// Whether the asset is a tar.gz archive that is extracted instead of copied.
+    pub fn is_archive(&self) -> bool {
+        self.filename.ends_with(".tar.gz") || self.filename.ends_with(".tgz")
+    }
+}
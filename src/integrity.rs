@@ -0,0 +1,105 @@
+use crate::error::XlmError;
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io,
+    path::{Component, Path},
+    time::UNIX_EPOCH,
+};
+
+/// Length of a hex-encoded SHA-256 hash.
+const SHA256_HEX_LENGTH: usize = 64;
+
+/// Compute the hex-encoded SHA-256 hash of a file.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("unable to open {path:?}"))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Compute the hex-encoded SHA-256 hash of some data.
+pub fn sha256_bytes(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Verify every file listed in a SHA-256 manifest matches its hash inside of the given directory.
+///
+/// Each non-empty line of the manifest should contain a relative path and its hex-encoded hash separated by
+/// whitespace, in either order so that both `path  hash` and `sha256sum`-style `hash  path` manifests are accepted.
+pub fn verify_sha256_manifest(dir: &Path, manifest: &str) -> Result<()> {
+    for (path, expected_hash) in manifest_entries(manifest)? {
+        let actual_hash = sha256_file(&dir.join(path))?;
+        if !actual_hash.eq_ignore_ascii_case(expected_hash) {
+            bail!(XlmError::ChecksumMismatch {
+                path: path.to_path_buf(),
+                expected: expected_hash.to_string(),
+                actual: actual_hash,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Compute a hex-encoded fingerprint of the size and modification time of every file listed in a SHA-256 manifest
+/// inside of the given directory, which changes whenever any of the files are modified without rehashing them.
+pub fn manifest_fingerprint(dir: &Path, manifest: &str) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for (path, _) in manifest_entries(manifest)? {
+        let full_path = dir.join(path);
+        let metadata =
+            fs::metadata(&full_path).with_context(|| format!("unable to read {full_path:?}"))?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?.as_nanos();
+        hasher.update(format!(
+            "{}\0{}\0{modified}\n",
+            path.display(),
+            metadata.len()
+        ));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Parse every entry of a SHA-256 manifest into its relative path and expected hash.
+fn manifest_entries(manifest: &str) -> Result<Vec<(&Path, &str)>> {
+    let mut entries = Vec::new();
+    for (index, line) in manifest.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((first, second)) = line.split_once(char::is_whitespace) else {
+            bail!(
+                "Line {} of manifest is not in the form 'path  hash'",
+                index + 1
+            );
+        };
+        let second = second.trim_start();
+        let (path, expected_hash) = if is_sha256_hex(second) {
+            (first, second)
+        } else if is_sha256_hex(first) {
+            // sha256sum marks binary mode files with a leading '*'.
+            (second.trim_start_matches('*'), first)
+        } else {
+            bail!(
+                "Line {} of manifest does not contain a SHA-256 hash",
+                index + 1
+            );
+        };
+
+        let path = Path::new(path);
+        if !path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            bail!("Manifest entry {path:?} points outside of the install directory");
+        }
+        entries.push((path, expected_hash));
+    }
+    Ok(entries)
+}
+
+/// Whether the given string is a hex-encoded SHA-256 hash.
+pub(crate) fn is_sha256_hex(s: &str) -> bool {
+    s.len() == SHA256_HEX_LENGTH && s.chars().all(|c| c.is_ascii_hexdigit())
+}
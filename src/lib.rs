@@ -0,0 +1,10 @@
+#[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+compile_error!("XLM only supports Linux x86_64");
+
+pub mod core;
+pub mod download;
+pub mod error;
+pub mod includes;
+pub mod install_manifest;
+pub mod integrity;
+pub mod signature;
@@ -0,0 +1,88 @@
+//! Writes logs to the systemd journal as an alternative to the log file, so they can be read with `journalctl`.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use simplelog::{Config, SharedLogger};
+use std::{fmt::Display, path::Path, str::FromStr};
+use systemd_journal_logger::JournalLog;
+
+/// The socket the systemd journal accepts log entries on.
+const JOURNAL_SOCKET_PATH: &str = "/run/systemd/journal/socket";
+/// The identifier log entries are tagged with in the journal.
+const JOURNAL_SYSLOG_IDENTIFIER: &str = "xlm";
+
+/// Where logs that would otherwise be written to the log file are sent.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogTarget {
+    #[default]
+    File,
+    Journal,
+}
+
+impl FromStr for LogTarget {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(Self::File),
+            "journal" => Ok(Self::Journal),
+            _ => Err("valid log targets are 'file' or 'journal'"),
+        }
+    }
+}
+
+impl Display for LogTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            LogTarget::File => write!(f, "file"),
+            LogTarget::Journal => write!(f, "journal"),
+        }
+    }
+}
+
+/// A logger that writes to the systemd journal, tagged with [`JOURNAL_SYSLOG_IDENTIFIER`].
+pub struct JournalLogger {
+    level: LevelFilter,
+    journal: JournalLog,
+}
+
+impl JournalLogger {
+    /// Create a logger for the systemd journal, returning `None` if the journal isn't available.
+    pub fn new(level: LevelFilter) -> Option<Box<Self>> {
+        if !Path::new(JOURNAL_SOCKET_PATH).exists() {
+            return None;
+        }
+        let journal = JournalLog::new()
+            .ok()?
+            .with_syslog_identifier(String::from(JOURNAL_SYSLOG_IDENTIFIER));
+        Some(Box::new(Self { level, journal }))
+    }
+}
+
+impl Log for JournalLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.journal.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.journal.flush();
+    }
+}
+
+impl SharedLogger for JournalLogger {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        self
+    }
+}
@@ -1,25 +1,51 @@
-#[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
-compile_error!("XLM only supports Linux x86_64");
-
 mod commands;
-mod includes;
+mod logging;
+mod preflight;
+mod protocol;
 mod ui;
 
 use anyhow::Result;
-use clap::Parser;
-use commands::{install_steam_tool::InstallSteamToolCommand, launch::LaunchCommand};
-use log::debug;
+use clap::{ArgAction, CommandFactory, Parser};
+use commands::{
+    check_libs::CheckLibsCommand, clean::CleanCommand, completions::CompletionsCommand,
+    install::InstallCommand, install_steam_tool::InstallSteamToolCommand, launch::LaunchCommand,
+    list_installed::ListInstalledCommand, rollback::RollbackCommand,
+    serve_mirror::ServeMirrorCommand, status::StatusCommand,
+};
+use log::{debug, info, warn};
+use logging::{JournalLogger, LogTarget};
+use reqwest::Url;
 use simplelog::{
-    ColorChoice, CombinedLogger, Config, LevelFilter, TermLogger, TerminalMode, WriteLogger,
+    ColorChoice, CombinedLogger, Config, LevelFilter, SharedLogger, TermLogger, TerminalMode,
+    WriteLogger,
+};
+use std::{
+    env,
+    ffi::OsString,
+    fs::{self, File},
+};
+use xlm::{
+    core::GITHUB_API_URL,
+    download::{configure_http_client, restrict_hosts, set_offline, IpVersion, DEFAULT_USER_AGENT},
+    includes::{get_logfile_path, get_previous_logfile_path},
 };
-use std::{env::temp_dir, fs::File};
 
 #[derive(Debug, Clone, Parser)]
 enum Command {
     Launch(Box<LaunchCommand>),
+    Install(Box<InstallCommand>),
     InstallSteamTool(InstallSteamToolCommand),
+    Clean(CleanCommand),
+    Status(StatusCommand),
+    Rollback(RollbackCommand),
+    ListInstalled(ListInstalledCommand),
+    CheckLibs(CheckLibsCommand),
+    Completions(CompletionsCommand),
+    #[clap(hide = true)]
+    ServeMirror(ServeMirrorCommand),
+    #[cfg(feature = "ui")]
     #[clap(hide = true)]
-    InternalLaunchUI,
+    InternalLaunchUI(ui::LaunchUIOptions),
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -28,6 +54,87 @@ struct Arguments {
     #[clap(subcommand)]
     command: Command,
 
+    /// Only send outbound requests to the configured XIVLauncher release source, signature url and aria source.
+    ///
+    /// This disables the self-updater and any other requests XLM would otherwise make, and logs each host
+    /// requests are allowed to be sent to.
+    #[clap(global = true, long = "no-network-except-configured")]
+    no_network_except_configured: bool,
+
+    /// Log what would be written, removed, launched or updated instead of doing it. Requests that only read
+    /// information, such as checking for XIVLauncher updates, are still sent.
+    #[clap(global = true, long = "dry-run", env = "XLM_DRY_RUN")]
+    dry_run: bool,
+
+    /// Skip the self-updater and any other optional outbound requests, such as checking for XIVLauncher updates
+    /// when it is already installed.
+    ///
+    /// Requests that are required to continue, like installing XIVLauncher when it isn't installed, are still sent.
+    #[clap(global = true, long = "offline", env = "XLM_OFFLINE")]
+    offline: bool,
+
+    /// List every endpoint XLM would send requests to and check that each is reachable, then exit without
+    /// installing or launching anything.
+    #[clap(global = true, long = "preflight")]
+    preflight: bool,
+
+    /// The User-Agent to send with all outbound requests.
+    ///
+    /// Some mirrors reject requests with an unknown User-Agent, which can be worked around by changing this.
+    #[clap(global = true, default_value = DEFAULT_USER_AGENT, long = "user-agent")]
+    user_agent: String,
+
+    /// The base url of the GitHub API to request release information from, such as a caching proxy in front of it.
+    ///
+    /// Release assets are still downloaded from the urls the API responds with.
+    #[clap(
+        global = true,
+        default_value = GITHUB_API_URL,
+        long = "github-api-url",
+        env = "XLM_GITHUB_API_URL"
+    )]
+    github_api_url: Url,
+
+    /// The IP version to download over, one of `auto`, `v4` or `v6`.
+    ///
+    /// Forcing `v4` can fix downloads hanging on networks with broken IPv6 connectivity.
+    #[clap(global = true, default_value_t = IpVersion::Auto, long = "ip-version")]
+    ip_version: IpVersion,
+
+    /// The maximum level of log messages to write to the terminal.
+    ///
+    /// One of `off`, `error`, `warn`, `info`, `debug` or `trace`.
+    #[clap(global = true, default_value_t = LevelFilter::Info, long = "term-log-level")]
+    term_log_level: LevelFilter,
+
+    /// Write more log messages to the terminal. Pass once for debug messages and twice for trace messages.
+    ///
+    /// Raises `--term-log-level` if it is lower.
+    #[clap(global = true, short = 'v', long = "verbose", action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only write errors to the terminal. Takes precedence over `--verbose` and `--term-log-level`.
+    #[clap(global = true, short = 'q', long = "quiet", env = "XLM_QUIET")]
+    quiet: bool,
+
+    /// The maximum level of log messages to write to the log file, or the systemd journal with `--log-target journal`.
+    ///
+    /// One of `off`, `error`, `warn`, `info`, `debug` or `trace`.
+    #[clap(global = true, default_value_t = LevelFilter::Debug, long = "file-log-level")]
+    file_log_level: LevelFilter,
+
+    /// Where to write logs besides the terminal, either `file` or `journal`.
+    ///
+    /// `journal` sends logs to the systemd journal tagged `xlm`, falling back to the log file if the journal is
+    /// unavailable.
+    #[clap(
+        global = true,
+        default_value_t = LogTarget::File,
+        long = "log-target",
+        env = "XLM_LOG_TARGET"
+    )]
+    log_target: LogTarget,
+
     /// The name of the GitHub repository owner that XLM should attempt to self-update from.
     #[cfg(not(debug_assertions))]
     #[cfg(feature = "self_update")]
@@ -49,47 +156,278 @@ struct Arguments {
     /// This should only be disabled if your connection to GitHub is poor or ratelimited.
     #[cfg(not(debug_assertions))]
     #[cfg(feature = "self_update")]
-    #[clap(global = true, default_value_t = false, long = "xlm-updater-disable")]
+    #[clap(
+        global = true,
+        default_value_t = false,
+        long = "xlm-updater-disable",
+        env = "XLM_UPDATER_DISABLE"
+    )]
     xlm_updater_disable: bool,
+
+    /// Only check whether a new version of XLM is available without downloading or installing it.
+    ///
+    /// Useful for read-only installations where the self-updater would be unable to replace the binary.
+    #[cfg(not(debug_assertions))]
+    #[cfg(feature = "self_update")]
+    #[clap(
+        global = true,
+        default_value_t = false,
+        long = "xlm-updater-check-only",
+        conflicts_with = "xlm_updater_disable"
+    )]
+    xlm_updater_check_only: bool,
+
+    /// Only update XLM once the latest release has been published for at least the given number of hours, to avoid
+    /// updating to a release that is quickly found to have a regression. `0` updates as soon as a release is published.
+    #[cfg(not(debug_assertions))]
+    #[cfg(feature = "self_update")]
+    #[clap(
+        global = true,
+        default_value_t = 0,
+        long = "xlm-updater-delay-hours",
+        env = "XLM_UPDATER_DELAY_HOURS",
+        value_name = "HOURS"
+    )]
+    xlm_updater_delay_hours: u64,
+}
+
+/// How long ago the latest release of the given GitHub repository was published.
+#[cfg(not(debug_assertions))]
+#[cfg(feature = "self_update")]
+async fn latest_release_age(owner: &str, repo: &str) -> Result<std::time::Duration> {
+    use anyhow::Context;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    let release = octocrab::instance()
+        .repos(owner, repo)
+        .releases()
+        .get_latest()
+        .await?;
+    let published_at = release
+        .published_at
+        .context("latest release has no publish date")?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    Ok(Duration::from_secs(
+        now.saturating_sub(published_at.timestamp()).max(0) as u64,
+    ))
+}
+
+/// Split the arguments Steam passed to the compatibility tool into the leading flags recognised by `xlm launch` and
+/// the arguments that are forwarded to XIVLauncher.
+fn split_steam_launch_flags(args: &[OsString]) -> (Vec<OsString>, Vec<OsString>) {
+    let command = Arguments::command();
+    let launch = command
+        .find_subcommand("launch")
+        .expect("the launch subcommand should exist");
+    let mut index = 0;
+    while let Some(flag) = args
+        .get(index)
+        .and_then(|arg| arg.to_str())
+        .and_then(|arg| arg.strip_prefix("--"))
+    {
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (flag, None),
+        };
+        let Some(arg) = command
+            .get_arguments()
+            .chain(launch.get_arguments())
+            .find(|arg| arg.get_long() == Some(name) && arg.get_id() != "steam_app_args")
+        else {
+            break;
+        };
+        index += match value.is_none() && arg.get_action().takes_values() {
+            true => 2,
+            false => 1,
+        };
+    }
+    let index = index.min(args.len());
+    (args[..index].to_vec(), args[index..].to_vec())
+}
+
+/// Move flags for `xlm launch` that Steam passed after `--steam-app-args` in front of it so they are applied instead
+/// of being forwarded to XIVLauncher, returning the moved flags.
+///
+/// Setups from before `XLM_LAUNCH_ARGS` existed set XLM's flags directly in Steam's launch options.
+fn hoist_steam_launch_flags(args: &mut Vec<OsString>) -> Vec<OsString> {
+    let Some(position) = args.iter().position(|arg| arg == "--steam-app-args") else {
+        return Vec::new();
+    };
+    let (flags, app_args) = split_steam_launch_flags(&args[position + 1..]);
+    args.truncate(position);
+    args.extend(flags.iter().cloned());
+    args.push(OsString::from("--steam-app-args"));
+    args.extend(app_args);
+    flags
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Arguments::parse();
+    let mut argv = env::args_os().collect();
+    let steam_launch_flags = hoist_steam_launch_flags(&mut argv);
+    let args = Arguments::parse_from(argv);
 
+    // Keep stdout free for protocol events and completion scripts.
+    let terminal_mode = match &args.command {
+        Command::Launch(cmd) if cmd.progress_protocol => TerminalMode::Stderr,
+        Command::Completions(_) => TerminalMode::Stderr,
+        _ => TerminalMode::Mixed,
+    };
+
+    let term_log_level = match (args.quiet, args.verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => args.term_log_level,
+        (false, 1) => args.term_log_level.max(LevelFilter::Debug),
+        (false, _) => LevelFilter::Trace,
+    };
+    let journal_logger = match args.log_target {
+        LogTarget::Journal => JournalLogger::new(args.file_log_level),
+        LogTarget::File => None,
+    };
+    let journal_unavailable = args.log_target == LogTarget::Journal && journal_logger.is_none();
+    let persistent_logger: Box<dyn SharedLogger> = match journal_logger {
+        Some(journal_logger) => journal_logger,
+        None => {
+            // The launch UI is started by another XLM process, so it must add to that process' log file.
+            #[cfg(feature = "ui")]
+            let is_launch_ui = matches!(args.command, Command::InternalLaunchUI(_));
+            #[cfg(not(feature = "ui"))]
+            let is_launch_ui = false;
+            if !is_launch_ui {
+                // Keep only the log of the previous run so logs don't accumulate across runs.
+                let _ = fs::rename(get_logfile_path(), get_previous_logfile_path());
+            }
+            // Append so the lines of both processes are kept when the launch UI is running.
+            let logfile = File::options()
+                .create(true)
+                .append(true)
+                .open(get_logfile_path());
+            WriteLogger::new(args.file_log_level, Config::default(), logfile.unwrap())
+        }
+    };
     CombinedLogger::init(vec![
         TermLogger::new(
-            LevelFilter::Info,
+            term_log_level,
             Config::default(),
-            TerminalMode::Mixed,
+            terminal_mode,
             ColorChoice::Auto,
         ),
-        WriteLogger::new(
-            LevelFilter::Debug,
-            Config::default(),
-            File::create(temp_dir().join(format!("{}.log", env!("CARGO_PKG_NAME")))).unwrap(),
-        ),
+        persistent_logger,
     ])?;
+    if journal_unavailable {
+        warn!(
+            "The systemd journal is unavailable, writing logs to {:?} instead",
+            get_logfile_path()
+        );
+    }
+
+    if !steam_launch_flags.is_empty() {
+        warn!(
+            "XLM flags set directly in Steam's launch options are deprecated, set them with `XLM_LAUNCH_ARGS=\"...\" %command%` instead: {steam_launch_flags:?}"
+        );
+    }
 
     debug!("XLM v{}", env!("CARGO_PKG_VERSION"));
 
+    configure_http_client(&args.user_agent, args.ip_version, &args.github_api_url)?;
+    if args.preflight {
+        let endpoints = match &args.command {
+            Command::Launch(cmd) => cmd.install_options.endpoints().await?,
+            Command::Install(cmd) => cmd.install_options.endpoints().await?,
+            _ => Vec::new(),
+        };
+        #[cfg(not(debug_assertions))]
+        #[cfg(feature = "self_update")]
+        let endpoints = match !args.xlm_updater_disable
+            && !args.no_network_except_configured
+            && !args.offline
+        {
+            true => [
+                endpoints,
+                xlm::core::ReleaseAssetInfo::github_endpoints(
+                    &args.xlm_updater_repo_owner,
+                    &args.xlm_updater_repo_name,
+                )?,
+            ]
+            .concat(),
+            false => endpoints,
+        };
+        return preflight::run(endpoints).await;
+    }
+    if args.no_network_except_configured {
+        restrict_hosts();
+    }
+    if args.offline {
+        set_offline();
+    }
+    if args.dry_run {
+        info!("Dry run enabled, nothing will be modified");
+        commands::set_dry_run();
+    }
+
     // Ensure the binary is up to date from GitHub releases.
     #[cfg(not(debug_assertions))]
     #[cfg(feature = "self_update")]
-    if !args.xlm_updater_disable {
+    if !args.xlm_updater_disable && !args.no_network_except_configured && !args.offline {
+        // Give new releases time to soak before updating to them, unless only checking for updates.
+        let delay_hours = args.xlm_updater_delay_hours;
+        let update_delayed = delay_hours > 0
+            && !args.xlm_updater_check_only
+            && match latest_release_age(&args.xlm_updater_repo_owner, &args.xlm_updater_repo_name)
+                .await
+            {
+                Ok(age) if age.as_secs() >= delay_hours * 60 * 60 => false,
+                Ok(age) => {
+                    info!(
+                        "Skipping the XLM self-updater as the latest release was published {}h ago, which is less than the {delay_hours}h update delay",
+                        age.as_secs() / 60 / 60
+                    );
+                    true
+                }
+                Err(err) => {
+                    warn!("Skipping the XLM self-updater as the publish date of the latest release could not be checked: {err:#}");
+                    true
+                }
+            };
         tokio::task::spawn_blocking(move || {
-            use log::info;
-            use self_update::cargo_crate_version;
-            info!("Running XLM self-updater");
-            let result = self_update::backends::github::Update::configure()
+            use self_update::{cargo_crate_version, version::bump_is_greater};
+            let updater = self_update::backends::github::Update::configure()
                 .repo_owner(&args.xlm_updater_repo_owner)
                 .repo_name(&args.xlm_updater_repo_name)
+                .with_url(args.github_api_url.as_str().trim_end_matches('/'))
                 .bin_name(env!("CARGO_PKG_NAME"))
                 .no_confirm(true)
                 .current_version(cargo_crate_version!())
                 .build()
-                .unwrap()
-                .update();
+                .unwrap();
+            if args.xlm_updater_check_only {
+                info!("Checking for XLM updates");
+                match updater.get_latest_release() {
+                    Ok(release) => {
+                        if bump_is_greater(cargo_crate_version!(), &release.version)
+                            .unwrap_or(false)
+                        {
+                            info!(
+                                "A new version of XLM is available: v{} (current: v{})",
+                                release.version,
+                                cargo_crate_version!()
+                            );
+                        } else {
+                            info!("XLM is up to date (v{})", cargo_crate_version!());
+                        }
+                    }
+                    Err(err) => eprintln!("XLM failed to check for updates: {:?}", err),
+                };
+                return;
+            }
+            if update_delayed {
+                return;
+            }
+            if args.dry_run {
+                info!("Would run the XLM self-updater");
+                return;
+            }
+            info!("Running XLM self-updater");
+            let result = updater.update();
             info!("XLM self-updater ran successfully");
             if let Err(result) = result {
                 eprintln!("XLM failed to auto-update: {:?}", result);
@@ -100,11 +438,116 @@ async fn main() -> Result<()> {
 
     // Run the command.
     match args.command {
-        Command::Launch(cmd) => cmd.run().await,
+        Command::Launch(cmd) => {
+            let progress_protocol = cmd.progress_protocol;
+            let result = cmd.run().await;
+            if let (true, Err(err)) = (progress_protocol, &result) {
+                protocol::emit_error(err);
+            }
+            result
+        }
+        Command::Install(cmd) => cmd.run().await,
         Command::InstallSteamTool(cmd) => cmd.run().await,
-        Command::InternalLaunchUI => {
-            ui::launch_ui_main();
+        Command::Clean(cmd) => cmd.run().await,
+        Command::Status(cmd) => cmd.run().await,
+        Command::Rollback(cmd) => cmd.run().await,
+        Command::ListInstalled(cmd) => cmd.run().await,
+        Command::CheckLibs(cmd) => cmd.run().await,
+        Command::Completions(cmd) => cmd.run(Arguments::command()).await,
+        Command::ServeMirror(cmd) => cmd.run().await,
+        #[cfg(feature = "ui")]
+        Command::InternalLaunchUI(options) => {
+            ui::launch_ui_main(options);
             Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn hoist_steam_launch_flags_moves_launch_flags() {
+        let mut args = os_args(&[
+            "xlm",
+            "launch",
+            "--steam-app-args",
+            "--use-fallback-secret-provider",
+            "--xlcore-repo-owner",
+            "owner",
+            "--skip-update",
+            "-game-arg",
+        ]);
+        let flags = hoist_steam_launch_flags(&mut args);
+        assert_eq!(
+            flags,
+            os_args(&[
+                "--use-fallback-secret-provider",
+                "--xlcore-repo-owner",
+                "owner",
+                "--skip-update"
+            ])
+        );
+        assert_eq!(
+            args,
+            os_args(&[
+                "xlm",
+                "launch",
+                "--use-fallback-secret-provider",
+                "--xlcore-repo-owner",
+                "owner",
+                "--skip-update",
+                "--steam-app-args",
+                "-game-arg"
+            ])
+        );
+        assert!(Arguments::try_parse_from(args).is_ok());
+    }
+
+    #[test]
+    fn hoist_steam_launch_flags_stops_at_unknown_argument() {
+        let mut args = os_args(&[
+            "xlm",
+            "launch",
+            "--steam-app-args",
+            "--xlcore-repo-name=xlcore",
+            "--not-an-xlm-flag",
+            "--skip-update",
+        ]);
+        let flags = hoist_steam_launch_flags(&mut args);
+        assert_eq!(flags, os_args(&["--xlcore-repo-name=xlcore"]));
+        assert_eq!(
+            args,
+            os_args(&[
+                "xlm",
+                "launch",
+                "--xlcore-repo-name=xlcore",
+                "--steam-app-args",
+                "--not-an-xlm-flag",
+                "--skip-update"
+            ])
+        );
+    }
+
+    #[test]
+    fn hoist_steam_launch_flags_keeps_app_args() {
+        let mut args = os_args(&["xlm", "launch", "--steam-app-args", "-nosteam", "--"]);
+        assert!(hoist_steam_launch_flags(&mut args).is_empty());
+        assert_eq!(
+            args,
+            os_args(&["xlm", "launch", "--steam-app-args", "-nosteam", "--"])
+        );
+    }
+
+    #[test]
+    fn hoist_steam_launch_flags_ignores_other_commands() {
+        let mut args = os_args(&["xlm", "install", "--skip-update"]);
+        assert!(hoist_steam_launch_flags(&mut args).is_empty());
+        assert_eq!(args, os_args(&["xlm", "install", "--skip-update"]));
+    }
+}
@@ -0,0 +1,49 @@
+//! Lists every endpoint XLM will send requests to for an invocation and checks that each is reachable, without
+//! installing or launching anything. Useful for building a firewall allowlist or diagnosing DNS and connectivity.
+
+use anyhow::{bail, Result};
+use log::{info, warn};
+use reqwest::Url;
+use std::{collections::HashSet, time::Instant};
+use xlm::download::check_reachable;
+
+pub async fn run(endpoints: Vec<Url>) -> Result<()> {
+    let mut seen = HashSet::new();
+    let endpoints = endpoints
+        .into_iter()
+        .filter(|endpoint| seen.insert(endpoint.clone()))
+        .collect::<Vec<_>>();
+    let mut hosts = endpoints
+        .iter()
+        .filter_map(|endpoint| endpoint.host_str())
+        .collect::<Vec<_>>();
+    hosts.sort_unstable();
+    hosts.dedup();
+    info!(
+        "XLM will send requests to the following hosts: {}",
+        hosts.join(", ")
+    );
+
+    let mut unreachable = 0;
+    for endpoint in &endpoints {
+        let start = Instant::now();
+        match check_reachable(endpoint).await {
+            Ok(status) => info!(
+                "{endpoint} is reachable (responded with {status} in {}ms)",
+                start.elapsed().as_millis()
+            ),
+            Err(err) => {
+                warn!("{endpoint} is unreachable: {err}");
+                unreachable += 1;
+            }
+        }
+    }
+    if unreachable > 0 {
+        bail!(
+            "{unreachable} of {} endpoints are unreachable",
+            endpoints.len()
+        );
+    }
+    info!("All {} endpoints are reachable", endpoints.len());
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! A stable line-based protocol written to stdout when launching with `--progress-protocol`, allowing other
+//! launchers to embed XLM and display its progress. Logs are written to stderr instead while it is enabled.
+//!
+//! Every event is written as a single line starting with the name of the event:
+//!
+//! - `PHASE <phase>`: XLM has moved onto a new phase, one of `checking`, `downloading`, `verifying`,
+//!   `extracting`, `finishing`, `launching` or `running`.
+//! - `PROGRESS <percent> <text>`: an estimate from 0 to 100 of how far through installing XIVLauncher XLM is,
+//!   followed by a short description of the current step.
+//! - `EXITED <code>`: XIVLauncher exited with the given exit code, or `unknown` if it was killed by a signal.
+//! - `ERROR <message>`: XLM failed with the given message.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+fn emit(event: &str, content: impl Display) {
+    // Events must stay on a single line so they can be read line-by-line.
+    let content = content.to_string().replace(['\r', '\n'], " ");
+    let _ = writeln!(io::stdout().lock(), "{event} {content}");
+}
+
+pub fn emit_phase(phase: impl Display) {
+    emit("PHASE", phase);
+}
+
+pub fn emit_progress(percent: u8, text: &str) {
+    emit("PROGRESS", format!("{} {text}", percent.min(100)));
+}
+
+pub fn emit_exited(code: Option<i32>) {
+    match code {
+        Some(code) => emit("EXITED", code),
+        None => emit("EXITED", "unknown"),
+    }
+}
+
+pub fn emit_error(err: &anyhow::Error) {
+    emit("ERROR", format!("{err:#}"));
+}
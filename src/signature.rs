@@ -0,0 +1,97 @@
+use anyhow::{bail, Context, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey, PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+use std::{fmt::Display, str::FromStr};
+
+/// Length of the key id that minisign prefixes keys and signatures with.
+const MINISIGN_KEY_ID_LENGTH: usize = 8;
+/// Length of the algorithm identifier that minisign prefixes keys and signatures with.
+const MINISIGN_ALGORITHM_LENGTH: usize = 2;
+/// Prefix of comment lines in minisign key and signature files.
+const MINISIGN_COMMENT_PREFIX: &str = "untrusted comment:";
+
+/// An ed25519 public key used for verifying detached signatures.
+///
+/// Accepts either a base64-encoded raw ed25519 key or a minisign public key.
+#[derive(Debug, Clone)]
+pub struct PublicKey(VerifyingKey);
+
+impl FromStr for PublicKey {
+    type Err = &'static str;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Allow passing the full contents of a minisign .pub file.
+        let encoded = s
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(MINISIGN_COMMENT_PREFIX))
+            .unwrap_or_default();
+        let bytes = BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|_| "public key is not valid base64")?;
+        let key_bytes = match bytes.len() {
+            PUBLIC_KEY_LENGTH => &bytes[..],
+            len if len
+                == MINISIGN_ALGORITHM_LENGTH + MINISIGN_KEY_ID_LENGTH + PUBLIC_KEY_LENGTH
+                && bytes.starts_with(b"Ed") =>
+            {
+                &bytes[MINISIGN_ALGORITHM_LENGTH + MINISIGN_KEY_ID_LENGTH..]
+            }
+            _ => return Err("public key must be a raw ed25519 key or a minisign public key"),
+        };
+        VerifyingKey::from_bytes(
+            key_bytes
+                .try_into()
+                .map_err(|_| "public key has an invalid length")?,
+        )
+        .map(Self)
+        .map_err(|_| "public key is not a valid ed25519 key")
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", BASE64_STANDARD.encode(self.0.as_bytes()))
+    }
+}
+
+impl PublicKey {
+    /// Verify data against a detached signature.
+    ///
+    /// The signature may be raw ed25519 signature bytes, base64-encoded signature bytes or a minisign signature file.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<()> {
+        let signature = Self::parse_signature(signature)?;
+        self.0
+            .verify(data, &signature)
+            .context("signature does not match the given public key")
+    }
+
+    fn parse_signature(content: &[u8]) -> Result<Signature> {
+        if content.len() == SIGNATURE_LENGTH {
+            return Ok(Signature::from_bytes(content.try_into()?));
+        }
+
+        let text = std::str::from_utf8(content).context("signature is not valid text")?;
+        let encoded = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with(MINISIGN_COMMENT_PREFIX))
+            .context("signature file is empty")?;
+        let bytes = BASE64_STANDARD
+            .decode(encoded)
+            .context("signature is not valid base64")?;
+        let signature_bytes = match bytes.len() {
+            SIGNATURE_LENGTH => &bytes[..],
+            len if len == MINISIGN_ALGORITHM_LENGTH + MINISIGN_KEY_ID_LENGTH + SIGNATURE_LENGTH => {
+                match &bytes[..MINISIGN_ALGORITHM_LENGTH] {
+                    b"Ed" => &bytes[MINISIGN_ALGORITHM_LENGTH + MINISIGN_KEY_ID_LENGTH..],
+                    b"ED" => bail!(
+                        "prehashed minisign signatures are not supported, please sign with 'minisign -l'"
+                    ),
+                    _ => bail!("signature uses an unknown minisign algorithm"),
+                }
+            }
+            _ => bail!("signature is not a valid ed25519 or minisign signature"),
+        };
+        Ok(Signature::from_bytes(signature_bytes.try_into()?))
+    }
+}
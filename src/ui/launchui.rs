@@ -1,58 +1,173 @@
+use clap::Args;
 use eframe::egui::{
     Align, CentralPanel, Direction, Layout, Spinner, TopBottomPanel, ViewportBuilder,
 };
+use log::{error, warn};
 use std::{
     io::{self, BufRead, Write},
-    sync::{mpsc, Arc, RwLock},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, RwLock,
+    },
 };
 
+/// The exit code of the launch UI process when a window could not be created, such as when running headless or
+/// without a usable graphics backend.
+pub const UI_UNAVAILABLE_EXIT_CODE: i32 = 3;
+
+/// Options controlling how the launch UI window is displayed.
+#[derive(Debug, Clone, Args)]
+pub struct LaunchUIOptions {
+    /// The scale factor of the launch UI, used to make it more legible on high or low DPI displays.
+    #[clap(
+        default_value_t = 1.5,
+        long = "ui-scale",
+        env = "XLM_UI_SCALE",
+        value_parser = parse_ui_scale
+    )]
+    ui_scale: f32,
+
+    /// Show the launch UI as a borderless fullscreen window instead of a small window, for handhelds such as the
+    /// Steam Deck where the small window can appear tiny or offset.
+    #[clap(long = "ui-fullscreen", env = "XLM_UI_FULLSCREEN")]
+    ui_fullscreen: bool,
+
+    /// Don't show the launch UI if less than the given number of megabytes of memory is available, to avoid running
+    /// out of memory on low-memory devices. Progress is still written to the terminal.
+    #[clap(
+        long = "min-free-memory-mb",
+        env = "XLM_MIN_FREE_MEMORY_MB",
+        value_name = "MB"
+    )]
+    min_free_memory_mb: Option<u64>,
+}
+
+impl LaunchUIOptions {
+    /// Convert these options back into arguments for the `internal-launch-ui` command.
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec![format!("--ui-scale={}", self.ui_scale)];
+        if self.ui_fullscreen {
+            args.push(String::from("--ui-fullscreen"));
+        }
+        args
+    }
+}
+
+/// Read the amount of memory available for new processes from `/proc/meminfo`, in megabytes.
+fn available_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kilobytes = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes / 1024)
+}
+
+fn parse_ui_scale(s: &str) -> Result<f32, String> {
+    let scale: f32 = s.parse().map_err(|err| format!("{err}"))?;
+    if !scale.is_finite() || scale <= 0.0 {
+        return Err(String::from("ui scale must be a number greater than 0"));
+    }
+    Ok(scale)
+}
+
 pub struct LaunchUI {
-    child: std::process::Child,
+    child: Mutex<std::process::Child>,
+    /// Whether the launch UI process has already been found to have exited.
+    exited: AtomicBool,
     _stdin_thread: Option<std::thread::JoinHandle<()>>,
     tx: mpsc::Sender<String>,
 }
 
 impl LaunchUI {
-    pub fn new() -> Self {
+    /// Spawn the launch UI as a child process, returning `None` if it could not be started.
+    pub fn new(options: &LaunchUIOptions) -> Option<Self> {
+        if let Some(min_free_memory_mb) = options.min_free_memory_mb {
+            match available_memory_mb() {
+                Some(available) if available < min_free_memory_mb => {
+                    warn!("Only {available}MB of memory is available (minimum {min_free_memory_mb}MB), continuing without the launch UI");
+                    return None;
+                }
+                Some(_) => {}
+                None => warn!("Unable to read available memory from /proc/meminfo, ignoring `--min-free-memory-mb`"),
+            }
+        }
+
         let (tx, rx) = mpsc::channel();
 
-        let mut child = std::process::Command::new(std::env::current_exe().unwrap());
+        let current_exe = match std::env::current_exe() {
+            Ok(exe) => exe,
+            Err(err) => {
+                warn!("Unable to locate the current executable to start the launch UI: {err:?}");
+                return None;
+            }
+        };
+        let mut child = std::process::Command::new(current_exe);
         #[cfg(not(debug_assertions))]
         #[cfg(feature = "self_update")]
         child.arg("--xlm-updater-disable");
         child
             .arg("internal-launch-ui")
+            .args(options.to_args())
             .stdin(std::process::Stdio::piped());
-        let mut child = child.spawn().unwrap();
+        let mut child = match child.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                warn!("Unable to start the launch UI, continuing without it: {err:?}");
+                return None;
+            }
+        };
 
-        let mut stdin = child.stdin.take().unwrap();
+        let mut stdin = child.stdin.take()?;
         let stdin_thread = std::thread::spawn(move || {
             for msg in rx.iter() {
-                writeln!(stdin, "{msg}").unwrap();
+                if writeln!(stdin, "{msg}").is_err() {
+                    break;
+                }
             }
         });
 
-        Self {
-            child,
+        Some(Self {
+            child: Mutex::new(child),
+            exited: AtomicBool::new(false),
             _stdin_thread: Some(stdin_thread),
             tx,
-        }
+        })
     }
 
     pub fn set_progress_text(&self, text: &str) {
-        self.tx.send(text.to_string()).unwrap();
+        if self.exited.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Ok(Some(status)) = self.child.lock().unwrap().try_wait() {
+            self.exited.store(true, Ordering::Relaxed);
+            match status.code() {
+                Some(UI_UNAVAILABLE_EXIT_CODE) => {
+                    warn!("The launch UI is unavailable on this system, continuing with terminal progress only")
+                }
+                _ => warn!("The launch UI exited unexpectedly ({status}), continuing with terminal progress only"),
+            }
+            return;
+        }
+        let _ = self.tx.send(text.to_string());
     }
 }
 
 impl Drop for LaunchUI {
     fn drop(&mut self) {
-        self.child.kill().unwrap();
+        let _ = self.child.get_mut().unwrap().kill();
     }
 }
 
 /// When launched with a flag, this will be used instead of the main xlm logic. This allows
 /// us to launch ourselves to show a UI without having to spawn a window from within Tokio.
-pub fn launch_ui_main() {
+///
+/// Exits with [`UI_UNAVAILABLE_EXIT_CODE`] if the window can't be created.
+pub fn launch_ui_main(options: LaunchUIOptions) {
     let progress_text = Arc::new(RwLock::new(String::new()));
     std::thread::spawn({
         let progress_text = progress_text.clone();
@@ -68,19 +183,23 @@ pub fn launch_ui_main() {
         }
     });
 
+    let viewport = ViewportBuilder::default().with_decorations(false);
+    let viewport = match options.ui_fullscreen {
+        true => viewport.with_fullscreen(true),
+        false => viewport
+            .with_inner_size([800.0, 500.0])
+            .with_resizable(false),
+    };
     eframe::run_simple_native(
         "XLM",
         eframe::NativeOptions {
             event_loop_builder: None,
             run_and_return: true,
-            viewport: ViewportBuilder::default()
-                .with_inner_size([800.0, 500.0])
-                .with_resizable(false)
-                .with_decorations(false),
+            viewport,
             ..Default::default()
         },
         move |ctx, _frame| {
-            ctx.set_pixels_per_point(1.5);
+            ctx.set_pixels_per_point(options.ui_scale);
             TopBottomPanel::bottom("bottom").show(ctx, |ui| {
                 ui.with_layout(Layout::left_to_right(Align::Min), |ui| {
                     ui.add(Spinner::default());
@@ -99,5 +218,8 @@ pub fn launch_ui_main() {
             });
         },
     )
-    .unwrap();
+    .unwrap_or_else(|err| {
+        error!("Unable to create the launch UI window: {err}");
+        std::process::exit(UI_UNAVAILABLE_EXIT_CODE);
+    });
 }
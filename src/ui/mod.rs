@@ -1,3 +1,31 @@
+#[cfg(feature = "ui")]
 mod launchui;
 
+#[cfg(feature = "ui")]
 pub use launchui::*;
+
+#[cfg(not(feature = "ui"))]
+pub use headless::*;
+
+/// Stand-ins for the launch UI when XLM is built without the `ui` feature.
+/// Progress is only reported through the terminal log in this case.
+#[cfg(not(feature = "ui"))]
+mod headless {
+    use clap::Args;
+
+    #[derive(Debug, Clone, Args)]
+    pub struct LaunchUIOptions {}
+
+    /// The launch UI can never be created without the `ui` feature.
+    pub enum LaunchUI {}
+
+    impl LaunchUI {
+        pub fn new(_options: &LaunchUIOptions) -> Option<Self> {
+            None
+        }
+
+        pub fn set_progress_text(&self, _text: &str) {
+            match *self {}
+        }
+    }
+}